@@ -0,0 +1,262 @@
+use std::{collections::HashMap, fs, io, path::PathBuf, str::FromStr};
+
+use crate::scanner::{
+    bounds::Bounds,
+    distance::{Metric, Origin, Scale},
+    groups::{BlockGroup, BlockGroups},
+    nbt_path::NbtPredicate,
+    points::Point,
+    shapes::{parse_area, Shape},
+    veins::Linking,
+};
+
+/// Command line arguments split into positional arguments and `--name value` options
+///
+/// Options may be written as `--name value` or `--name=value`. An option that is
+/// followed by another option (or nothing at all) is a switch with no value, as are
+/// the `SWITCHES`, which never take the next argument.
+pub struct Args {
+    pub positional: Vec<String>,
+    pub options: HashMap<String, Vec<String>>,
+}
+
+/// Options that never have a value, so a positional argument after them stays one
+const SWITCHES: [&str; 6] = [
+    "exposure",
+    "mirror",
+    "rotate",
+    "round-trip",
+    "vein-stats",
+    "viability",
+];
+
+impl Args {
+    pub fn parse(args: impl Iterator<Item = String>) -> Self {
+        let mut positional = vec![];
+        let mut options = HashMap::<String, Vec<String>>::new();
+        let mut args = args.peekable();
+
+        while let Some(arg) = args.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                positional.push(arg);
+                continue;
+            };
+
+            let (name, value) = match name.split_once('=') {
+                Some((name, value)) => (name.to_string(), value.to_string()),
+                None => match args.peek() {
+                    Some(next) if !next.starts_with("--") && !SWITCHES.contains(&name) => {
+                        (name.to_string(), args.next().unwrap())
+                    }
+                    _ => (name.to_string(), String::new()),
+                },
+            };
+
+            options.entry(name).or_default().push(value);
+        }
+
+        Self {
+            positional,
+            options,
+        }
+    }
+
+    pub fn positional(&self, index: usize, name: &str) -> Result<&str, io::Error> {
+        self.positional
+            .get(index)
+            .map(String::as_str)
+            .ok_or_else(|| invalid_input(format!("Missing argument: {}", name)))
+    }
+
+    /// The arguments that follow a command name
+    pub fn shift(mut self) -> Self {
+        if !self.positional.is_empty() {
+            self.positional.remove(0);
+        }
+
+        self
+    }
+
+    /// The last value given for an option
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options
+            .get(name)
+            .and_then(|values| values.last())
+            .map(String::as_str)
+    }
+
+    /// Every value given for an option that may be repeated
+    pub fn options(&self, name: &str) -> impl Iterator<Item = &str> {
+        self.options
+            .get(name)
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.options.contains_key(name)
+    }
+}
+
+/// The dimension, origin and area that a command searches
+pub struct Search {
+    pub path: PathBuf,
+    pub origin: Origin,
+    pub area: Box<dyn Shape>,
+}
+
+impl Search {
+    /// Read the dimension path (the first argument) along with the origin and threshold
+    /// found at the given position and the one after it
+    pub fn parse(args: &Args, origin_index: usize) -> Result<Self, io::Error> {
+        let path = PathBuf::from(args.positional(0, "path_to_dimension")?);
+        let home = parse_point(
+            args.positional
+                .get(origin_index)
+                .map_or("0,8,0", String::as_str),
+        )?;
+        let threshold = parse_point(
+            args.positional
+                .get(origin_index + 1)
+                .map_or("100,8,100", String::as_str),
+        )?;
+        let dimension = Scale::of_dimension(&path);
+
+        let origin = Origin {
+            point: home,
+            scale: parse_option(args, "origin-scale")?.unwrap_or(dimension),
+            dimension,
+            metric: parse_option(args, "metric")?.unwrap_or(Metric::Euclidean),
+        };
+
+        // An explicit area replaces the box around the origin
+        let area: Box<dyn Shape> = match args.option("area") {
+            Some(expr) => parse_area(expr).map_err(|err| invalid_input(err.to_string()))?,
+            None => Box::new(
+                Bounds::from_point(&origin.local()).expand(&origin.local_extent(&threshold)),
+            ),
+        };
+
+        Ok(Self { path, origin, area })
+    }
+}
+
+/// How veins are linked, from `--connectivity` and `--max-gap`
+pub fn parse_linking(args: &Args) -> Result<Linking, io::Error> {
+    let default = Linking::default();
+
+    Ok(Linking {
+        connectivity: parse_option(args, "connectivity")?.unwrap_or(default.connectivity),
+        max_gap: parse_option(args, "max-gap")?.unwrap_or(default.max_gap),
+    })
+}
+
+/// Block groups read from a `--groups` file followed by each `--group name=blocks`
+pub fn parse_groups(args: &Args) -> Result<BlockGroups, io::Error> {
+    let mut groups = match args.option("groups") {
+        Some(path) => BlockGroups::parse(&fs::read_to_string(path)?)
+            .map_err(|err| invalid_input(format!("{}: {}", path, err)))?,
+        None => BlockGroups::default(),
+    };
+
+    for definition in args.options("group") {
+        groups
+            .0
+            .push(BlockGroup::parse(definition).map_err(|err| invalid_input(err.to_string()))?);
+    }
+
+    Ok(groups)
+}
+
+/// Every `--nbt path=value` predicate
+pub fn parse_nbt_predicates(args: &Args) -> Result<Vec<NbtPredicate>, io::Error> {
+    args.options("nbt")
+        .map(|predicate| {
+            predicate
+                .parse()
+                .map_err(|err| invalid_input(format!("--nbt: {}", err)))
+        })
+        .collect()
+}
+
+pub fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// Parse the value of an option if it was given
+pub fn parse_option<T>(args: &Args, name: &str) -> Result<Option<T>, io::Error>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    args.option(name)
+        .map(|value| {
+            value
+                .parse::<T>()
+                .map_err(|err| invalid_input(format!("--{}: {}", name, err)))
+        })
+        .transpose()
+}
+
+/// Parse a comma separated list of values if the option was given
+pub fn parse_list<T>(args: &Args, name: &str) -> Result<Option<Vec<T>>, io::Error>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    args.option(name)
+        .map(|values| {
+            values
+                .split(',')
+                .map(|value| value.trim().parse::<T>())
+                .collect::<Result<Vec<T>, _>>()
+                .map_err(|err| invalid_input(format!("--{}: {}", name, err)))
+        })
+        .transpose()
+}
+
+/// Parse an `x,y,z` triple
+pub fn parse_point(value: &str) -> Result<Point, io::Error> {
+    let parts = value
+        .split(',')
+        .map(|part| part.trim().parse::<i64>())
+        .collect::<Result<Vec<i64>, _>>()
+        .map_err(|err| invalid_input(format!("Invalid point '{}': {}", value, err)))?;
+
+    match parts.as_slice() {
+        [x, y, z] => Ok(Point {
+            x: *x,
+            y: *y,
+            z: *z,
+        }),
+        _ => Err(invalid_input(format!(
+            "Invalid point '{}': expected x,y,z",
+            value
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Args {
+        Args::parse(line.split_whitespace().map(str::to_string))
+    }
+
+    #[test]
+    fn switches_leave_positionals_alone() {
+        let parsed = args("--vein-stats world *diamond_ore --per blocks --exposure");
+
+        assert_eq!(parsed.positional, vec!["world", "*diamond_ore"]);
+        assert_eq!(parsed.option("per"), Some("blocks"));
+        assert!(parsed.flag("vein-stats"));
+        assert!(parsed.flag("exposure"));
+
+        // Other options still take the argument after them
+        let parsed = args("--mob zombie world --rotate 0,8,0");
+        assert_eq!(parsed.option("mob"), Some("zombie"));
+        assert_eq!(parsed.positional, vec!["world", "0,8,0"]);
+    }
+}
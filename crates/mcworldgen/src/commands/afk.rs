@@ -0,0 +1,89 @@
+use std::io::{self, Write};
+
+use crate::{
+    cli::{parse_option, Args, Search},
+    commands::spawners::find_spawners,
+    scanner::{
+        afk::{find_afk_spots, DEFAULT_ACTIVATION_RANGE, MAX_ACTIVATION_RANGE},
+        distance::Metric,
+    },
+};
+
+/// `afk <path_to_dimension> [origin] [threshold]`
+///
+/// Find the places to stand that keep the most spawners active at the same time,
+/// using each spawner's own activation range.
+pub fn run(args: Args) -> io::Result<()> {
+    let mut stdout = io::stdout();
+
+    let search = Search::parse(&args, 1)?;
+    let origin = &search.origin;
+    let min_spawners = parse_option(&args, "min-spawners")?.unwrap_or(2);
+
+    let spawners = find_spawners(&args, &search)?;
+    let ranges = spawners
+        .iter()
+        .map(|entity| {
+            let range = entity
+                .activation_range
+                .map_or(DEFAULT_ACTIVATION_RANGE, |range| range as i64);
+
+            (entity.pos(), range)
+        })
+        .collect::<Vec<_>>();
+
+    for (spawner, range) in ranges
+        .iter()
+        .filter(|(_, range)| *range > MAX_ACTIVATION_RANGE)
+    {
+        writeln!(
+            io::stderr(),
+            "Warning: the spawner at {}, {}, {} has an activation range of {} blocks, only the closest {} are searched",
+            spawner.x, spawner.y, spawner.z, range, MAX_ACTIVATION_RANGE
+        )?;
+    }
+
+    let spots = find_afk_spots(&ranges, min_spawners);
+
+    writeln!(
+        stdout,
+        "Found {} spots activating at least {} of {} spawners",
+        spots.len(),
+        min_spawners,
+        spawners.len()
+    )?;
+
+    for (num, spot) in spots.iter().enumerate() {
+        let position = &spot.position;
+
+        writeln!(
+            stdout,
+            "Spot {} at {}, {}, {} ({:.1}m away): {} spawners, {} positions",
+            num,
+            position.x,
+            position.y,
+            position.z,
+            origin.distance_to(position),
+            spot.spawners.len(),
+            spot.positions
+        )?;
+
+        for index in &spot.spawners {
+            let (spawner, range) = &ranges[*index];
+            let entity = &spawners[*index];
+
+            writeln!(
+                stdout,
+                "    {} at {}, {}, {} ({:.1}m of {} blocks)",
+                entity.mobs().first().copied().unwrap_or("unknown mob"),
+                spawner.x,
+                spawner.y,
+                spawner.z,
+                Metric::Euclidean.distance(position, spawner),
+                range.min(&MAX_ACTIVATION_RANGE)
+            )?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use std::io::{self, Write};
+
+use itertools::Itertools;
+use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::{
+    cli::{invalid_input, parse_groups, parse_linking, parse_list, Args, Search},
+    scanner::{
+        blocks::BlockPatterns,
+        branch::{simulate, Axis, Strategy, TunnelShape},
+        locate::locate,
+        lookup::BlockLookup,
+        veins::find_veins,
+        voxels::VoxelStore,
+    },
+};
+
+/// `branch <path_to_dimension> <blocks_to_find> [origin] [threshold]`
+///
+/// Simulate branch mining through the real blocks of the search area and compare
+/// how much ore each combination of Y level, spacing, tunnel shape and direction finds.
+pub fn run(args: Args) -> io::Result<()> {
+    let mut stdout = io::stdout();
+
+    let search = Search::parse(&args, 2)?;
+    let patterns = BlockPatterns::parse(args.positional(1, "blocks_to_find")?);
+    let linking = parse_linking(&args)?;
+    let groups = parse_groups(&args)?;
+    let origin = search.origin.local();
+
+    let levels = parse_list(&args, "y")?.unwrap_or(vec![origin.y]);
+    let spacings = parse_list(&args, "spacing")?.unwrap_or(vec![2]);
+    let shapes = parse_list(&args, "shape")?.unwrap_or(vec![TunnelShape {
+        width: 1,
+        height: 2,
+    }]);
+    let axes = parse_list(&args, "direction")?.unwrap_or(vec![Axis::X]);
+
+    // Tunnels are only dug inside the area, so they'd find nothing at other levels
+    let bounds = search.area.bounds();
+    if let Some(y) = levels
+        .iter()
+        .find(|y| !(bounds.min.y..=bounds.max.y).contains(*y))
+    {
+        return Err(invalid_input(format!(
+            "--y: {} is outside of the search area, which spans Y {} to {}",
+            y, bounds.min.y, bounds.max.y
+        )));
+    }
+
+    let summary = locate(&search.path, search.area.as_ref(), &patterns)?
+        .replacing_blocks(|blocks| groups.assign(blocks));
+
+    let veins = find_veins(summary.found, linking).collect_vec();
+    let vein_sizes = veins.iter().map(|vein| vein.blocks.len()).collect_vec();
+    let ore = VoxelStore::of_veins(&veins);
+
+    writeln!(
+        stdout,
+        "Simulating branch mining through {} blocks in {} veins",
+        ore.len(),
+        veins.len()
+    )?;
+
+    let strategies = levels
+        .iter()
+        .cartesian_product(&spacings)
+        .cartesian_product(&shapes)
+        .cartesian_product(&axes)
+        .map(|(((y, spacing), shape), axis)| Strategy {
+            y: *y,
+            spacing: *spacing,
+            shape: *shape,
+            axis: *axis,
+        })
+        .collect_vec();
+
+    let results = strategies
+        .par_iter()
+        .map(|strategy| {
+            let mut lookup = BlockLookup::new(&search.path);
+            let result = simulate(
+                strategy,
+                &origin,
+                search.area.as_ref(),
+                &ore,
+                &vein_sizes,
+                &mut lookup,
+            );
+
+            (strategy, result)
+        })
+        .collect::<Vec<_>>();
+
+    writeln!(
+        stdout,
+        "{:>6} {:>7} {:>6} {:>4} {:>9} {:>9} {:>10} {:>6} {:>10} {:>10}",
+        "y",
+        "spacing",
+        "shape",
+        "dir",
+        "mined",
+        "revealed",
+        "per 1000",
+        "veins",
+        "vein ore",
+        "per 1000"
+    )?;
+
+    let per_1000 = |count: usize, mined: usize| count as f64 / mined.max(1) as f64 * 1000.0;
+
+    for (strategy, result) in results.iter().sorted_by(|a, b| {
+        per_1000(b.1.revealed, b.1.mined).total_cmp(&per_1000(a.1.revealed, a.1.mined))
+    }) {
+        writeln!(
+            stdout,
+            "{:>6} {:>7} {:>6} {:>4} {:>9} {:>9} {:>10.2} {:>6} {:>10} {:>10.2}",
+            strategy.y,
+            strategy.spacing,
+            strategy.shape.to_string(),
+            strategy.axis.to_string(),
+            result.mined,
+            result.revealed,
+            per_1000(result.revealed, result.mined),
+            result.veins,
+            result.vein_blocks,
+            per_1000(result.vein_blocks, result.mined)
+        )?;
+    }
+
+    Ok(())
+}
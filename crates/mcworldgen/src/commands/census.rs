@@ -0,0 +1,67 @@
+use std::io::{self, Write};
+
+use crate::{
+    cli::{Args, Search},
+    scanner::{
+        census::{census_region, Census},
+        scan::scan_regions,
+    },
+};
+
+/// `census <path_to_dimension> [origin] [threshold]`
+///
+/// Count every block type inside the search area.
+pub fn run(args: Args) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    let search = Search::parse(&args, 1)?;
+
+    let (results, skipped_regions) =
+        scan_regions(&search.path, search.area.as_ref(), |file, rx, rz| {
+            census_region(search.area.as_ref(), file, rx, rz)
+        })?;
+
+    let regions = results.len();
+    let census = Census::combine(results.into_iter());
+
+    writeln!(
+        stdout,
+        "Scanned {} regions ({} skipped)",
+        regions, skipped_regions
+    )?;
+    writeln!(stdout, "Scanned {} chunks", census.chunks)?;
+    writeln!(stdout, "Scanned {} sections", census.sections)?;
+    writeln!(
+        stdout,
+        "Counted {} blocks ({} air, {} non-air)",
+        census.total(),
+        census.air(),
+        census.non_air()
+    )?;
+
+    let non_air = census.non_air().max(1) as f64;
+
+    for (namespace, blocks) in census.by_namespace() {
+        let total = blocks.iter().map(|(_, count)| count).sum::<u64>();
+
+        writeln!(
+            stdout,
+            "{} ({} blocks, {:.2}%)",
+            namespace,
+            total,
+            total as f64 / non_air * 100.0
+        )?;
+
+        for (name, count) in blocks {
+            writeln!(
+                stdout,
+                "  {:<48} {:>12} {:>8.4}% {:>10.0} per million",
+                name,
+                count,
+                count as f64 / non_air * 100.0,
+                count as f64 / non_air * 1_000_000.0
+            )?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,259 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use itertools::Itertools;
+
+use crate::{
+    cli::{parse_groups, parse_linking, Args, Search},
+    scanner::{
+        blocks::{count_variants, BlockPatterns},
+        locate::locate,
+        saved::load_scan,
+        scan::ScanResult,
+        shapes::Shape,
+        stats::{compare_distributions, compare_rates, mean, quantile, Significance},
+        veins::{find_veins, Linking},
+    },
+};
+
+/// `compare <world_or_scan_a> <world_or_scan_b> <blocks_to_find> [origin] [threshold]`
+///
+/// Compare the blocks found in two dimensions, or two scans saved with `--save`.
+/// Everything is normalized by the number of chunks scanned so worlds of
+/// different sizes can be compared.
+pub fn run(args: Args) -> io::Result<()> {
+    let mut stdout = io::stdout();
+
+    let search = Search::parse(&args, 3)?;
+    let other = PathBuf::from(args.positional(1, "world_or_scan_b")?);
+    let patterns = BlockPatterns::parse(args.positional(2, "blocks_to_find")?);
+
+    let groups = parse_groups(&args)?;
+
+    let a = scan_or_load(&search.path, search.area.as_ref(), &patterns)?
+        .replacing_blocks(|blocks| groups.assign(blocks));
+    let b = scan_or_load(&other, search.area.as_ref(), &patterns)?
+        .replacing_blocks(|blocks| groups.assign(blocks));
+
+    writeln!(stdout, "A: {} ({} chunks)", search.path.display(), a.chunks)?;
+    writeln!(stdout, "B: {} ({} chunks)", other.display(), b.chunks)?;
+
+    let a_groups = count_variants(a.found.iter());
+    let b_groups = count_variants(b.found.iter());
+    let groups = a_groups
+        .keys()
+        .chain(b_groups.keys())
+        .collect::<BTreeSet<_>>();
+
+    let empty = BTreeMap::new();
+
+    writeln!(stdout)?;
+    writeln!(stdout, "Blocks per chunk")?;
+    writeln!(
+        stdout,
+        "  {:<44} {:>10} {:>10} {:>9} {:>8} {:>9}",
+        "block", "A", "B", "change", "z", "p"
+    )?;
+
+    for group in &groups {
+        let a_variants = a_groups.get(*group).unwrap_or(&empty);
+        let b_variants = b_groups.get(*group).unwrap_or(&empty);
+
+        write_count_row(
+            &mut stdout,
+            group,
+            a_variants.values().sum(),
+            a.chunks,
+            b_variants.values().sum(),
+            b.chunks,
+        )?;
+
+        let variants = a_variants
+            .keys()
+            .chain(b_variants.keys())
+            .collect::<BTreeSet<_>>();
+
+        if variants.len() > 1 {
+            for variant in variants {
+                write_count_row(
+                    &mut stdout,
+                    &format!("  {}", variant),
+                    a_variants.get(variant).copied().unwrap_or(0),
+                    a.chunks,
+                    b_variants.get(variant).copied().unwrap_or(0),
+                    b.chunks,
+                )?;
+            }
+        }
+    }
+
+    writeln!(stdout)?;
+    writeln!(stdout, "Y distribution")?;
+    writeln!(
+        stdout,
+        "  {:<44} {:>10} {:>10} {:>9} {:>8} {:>9}",
+        "block", "mean A", "mean B", "shift", "KS D", "p"
+    )?;
+
+    for group in &groups {
+        let a_levels = levels(&a, group);
+        let b_levels = levels(&b, group);
+
+        write_distribution_row(&mut stdout, group, &a_levels, &b_levels, mean)?;
+    }
+
+    let linking = parse_linking(&args)?;
+    let a_veins = vein_sizes(a, linking);
+    let b_veins = vein_sizes(b, linking);
+
+    writeln!(stdout)?;
+    writeln!(stdout, "Vein sizes")?;
+    writeln!(
+        stdout,
+        "  {:<44} {:>10} {:>10} {:>9} {:>8} {:>9}",
+        "block", "median A", "median B", "shift", "KS D", "p"
+    )?;
+
+    for group in &groups {
+        let a_sizes = a_veins.sizes.get(*group).cloned().unwrap_or_default();
+        let b_sizes = b_veins.sizes.get(*group).cloned().unwrap_or_default();
+
+        write_distribution_row(&mut stdout, group, &a_sizes, &b_sizes, |sizes| {
+            quantile(sizes, 0.5)
+        })?;
+    }
+
+    writeln!(stdout)?;
+    writeln!(stdout, "Veins per chunk")?;
+    writeln!(
+        stdout,
+        "  {:<44} {:>10} {:>10} {:>9} {:>8} {:>9}",
+        "block", "A", "B", "change", "z", "p"
+    )?;
+
+    for group in &groups {
+        write_count_row(
+            &mut stdout,
+            group,
+            a_veins.sizes.get(*group).map_or(0, Vec::len),
+            a_veins.chunks,
+            b_veins.sizes.get(*group).map_or(0, Vec::len),
+            b_veins.chunks,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Scan a dimension, or load a scan that was saved to a file
+///
+/// Only the saved blocks inside the area are kept. The saved counters can't be
+/// narrowed down though, so a scan should be saved with the area it's compared in.
+fn scan_or_load(path: &Path, area: &dyn Shape, patterns: &BlockPatterns) -> io::Result<ScanResult> {
+    if !path.is_file() {
+        return locate(path, area, patterns);
+    }
+
+    let scan = load_scan(path)?.filtering_blocks(|block| patterns.matches(&block.name));
+    let total = scan.found.len();
+    let scan = scan.filtering_blocks(|block| area.contains(&block.pos()));
+
+    if scan.found.len() < total {
+        writeln!(
+            io::stderr(),
+            "Warning: {} of the {} blocks in {} are outside the search area, its counts are still those of the area it was saved with",
+            total - scan.found.len(),
+            total,
+            path.display()
+        )?;
+    }
+
+    Ok(scan)
+}
+
+fn levels(scan: &ScanResult, group: &str) -> Vec<f64> {
+    scan.found
+        .iter()
+        .filter(|block| block.group == group)
+        .map(|block| block.y as f64)
+        .sorted_by(f64::total_cmp)
+        .collect()
+}
+
+struct VeinSizes {
+    chunks: usize,
+    sizes: BTreeMap<String, Vec<f64>>,
+}
+
+fn vein_sizes(scan: ScanResult, linking: Linking) -> VeinSizes {
+    let sizes = find_veins(scan.found, linking)
+        .filter(|vein| vein.blocks.len() > 1)
+        .map(|vein| (vein.blocks[0].group.clone(), vein.blocks.len() as f64))
+        .into_group_map()
+        .into_iter()
+        .map(|(group, sizes)| (group, sizes.into_iter().sorted_by(f64::total_cmp).collect()))
+        .collect();
+
+    VeinSizes {
+        chunks: scan.chunks,
+        sizes,
+    }
+}
+
+fn write_count_row(
+    out: &mut impl Write,
+    label: &str,
+    a: usize,
+    a_chunks: usize,
+    b: usize,
+    b_chunks: usize,
+) -> io::Result<()> {
+    let a_rate = a as f64 / a_chunks.max(1) as f64;
+    let b_rate = b as f64 / b_chunks.max(1) as f64;
+    let change = match (a, b) {
+        (0, 0) => "-".to_string(),
+        (0, _) => "new".to_string(),
+        _ => format!("{:+.1}%", (b_rate - a_rate) / a_rate * 100.0),
+    };
+
+    let Significance { statistic, p } =
+        compare_rates(b as u64, b_chunks as f64, a as u64, a_chunks as f64);
+
+    writeln!(
+        out,
+        "  {:<44} {:>10.3} {:>10.3} {:>9} {:>8.2} {:>9.4}",
+        label, a_rate, b_rate, change, statistic, p
+    )
+}
+
+fn write_distribution_row(
+    out: &mut impl Write,
+    label: &str,
+    a: &[f64],
+    b: &[f64],
+    summarize: impl Fn(&[f64]) -> Option<f64>,
+) -> io::Result<()> {
+    let (a_value, b_value) = (summarize(a), summarize(b));
+
+    let format = |value: Option<f64>| value.map_or("-".to_string(), |v| format!("{:.1}", v));
+    let shift = match (a_value, b_value) {
+        (Some(a), Some(b)) => format!("{:+.1}", b - a),
+        _ => "-".to_string(),
+    };
+
+    let Significance { statistic, p } = compare_distributions(a, b);
+
+    writeln!(
+        out,
+        "  {:<44} {:>10} {:>10} {:>9} {:>8.3} {:>9.4}",
+        label,
+        format(a_value),
+        format(b_value),
+        shift,
+        statistic,
+        p
+    )
+}
@@ -0,0 +1,135 @@
+use std::io::{self, Write};
+
+use fastnbt::Value;
+use itertools::Itertools;
+use wildmatch::WildMatch;
+
+use crate::{
+    cli::{parse_nbt_predicates, parse_option, Args, Search},
+    commands::items::{describe, parse_enchantment},
+    scanner::{
+        blocks::BlockPatterns,
+        entities::{scan_entities, Entity, EntityQuery, Trade},
+        items::{Item, ItemQuery},
+    },
+};
+
+/// `entities <path_to_dimension> <entities_to_find> [origin] [threshold]`
+///
+/// Find mobs, pets and villagers by id, custom name (`--name`, ignoring case), the
+/// UUID of the player that tamed them (`--owner`), villager profession and level
+/// (`--profession`, `--level`) and the items they sell (`--sells`, optionally with
+/// `--enchantment`). `--nbt` predicates apply to the entity's own NBT.
+pub fn run(args: Args) -> io::Result<()> {
+    let mut stdout = io::stdout();
+
+    let search = Search::parse(&args, 2)?;
+    let origin = &search.origin;
+    let search_entities = args.positional(1, "entities_to_find")?;
+
+    let enchantment = args
+        .option("enchantment")
+        .map(parse_enchantment)
+        .transpose()?;
+    let sells = match (args.option("sells"), enchantment) {
+        (None, None) => None,
+        (items, enchantment) => Some(ItemQuery {
+            ids: BlockPatterns::parse(items.unwrap_or("*")),
+            name: None,
+            enchantment,
+        }),
+    };
+
+    let query = EntityQuery {
+        ids: BlockPatterns::parse(search_entities),
+        name: args
+            .option("name")
+            .map(|name| WildMatch::new(&name.to_lowercase())),
+        owner: args.option("owner").map(str::to_string),
+        profession: args.option("profession").map(WildMatch::new),
+        min_level: parse_option(&args, "level")?,
+        sells,
+    };
+
+    let predicates = parse_nbt_predicates(&args)?;
+
+    writeln!(stdout, "Looking for {}", search_entities)?;
+
+    let area = search.area.as_ref();
+    let entities = scan_entities(&search.path, area, |nbt: Value| {
+        let entity = Entity::from_nbt(nbt)?;
+        let position = entity.position()?;
+
+        let matches = area.contains(&position)
+            && query.matches(&entity)
+            && predicates
+                .iter()
+                .all(|predicate| predicate.matches(&entity.nbt));
+
+        matches.then_some((entity, position))
+    })?
+    .into_iter()
+    .map(|(entity, position)| {
+        let distance = origin.distance_to(&position);
+        (entity, position, distance)
+    })
+    .sorted_by(|a, b| a.2.total_cmp(&b.2))
+    .collect_vec();
+
+    writeln!(stdout, "Found {} entities", entities.len())?;
+
+    for (entity, position, distance) in &entities {
+        let mut details = vec![];
+
+        if let Some(villager) = &entity.villager {
+            details.push(format!(
+                "{} level {}",
+                villager.profession.as_deref().unwrap_or("no profession"),
+                villager.level.unwrap_or(1)
+            ));
+        }
+
+        if let Some(owner) = entity.owner() {
+            details.push(format!("owned by {}", owner));
+        }
+
+        writeln!(
+            stdout,
+            "{}{} at {}, {}, {} ({:.1}m away){}",
+            entity.id,
+            entity
+                .custom_name()
+                .map_or(String::new(), |name| format!(" \"{}\"", name)),
+            position.x,
+            position.y,
+            position.z,
+            distance,
+            match details.is_empty() {
+                true => String::new(),
+                false => format!(": {}", details.join(", ")),
+            }
+        )?;
+
+        for trade in entity.trades() {
+            writeln!(stdout, "    {}", describe_trade(trade))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// e.g. `sells 1 minecraft:enchanted_book [minecraft:mending 1] for 12 minecraft:emerald + 1 minecraft:book`
+fn describe_trade(trade: &Trade) -> String {
+    let stack = |item: &Item| format!("{} {}", item.count(), describe(item));
+
+    format!(
+        "sells {} for {}",
+        trade.sell.as_ref().map_or("nothing".to_string(), stack),
+        [&trade.buy, &trade.buy_b]
+            .into_iter()
+            .flatten()
+            .filter(|item| !item.is_empty())
+            .map(stack)
+            .join(" + ")
+    )
+}
@@ -0,0 +1,87 @@
+use std::io::{self, Write};
+
+use itertools::Itertools;
+
+use crate::{
+    cli::{parse_option, Args, Search},
+    scanner::{
+        blocks::BlockPatterns,
+        fluids::{find_fluid_bodies, locate_fluids},
+    },
+};
+
+/// `fluids <path_to_dimension> <fluids_to_find> [origin] [threshold]`
+///
+/// Flood fill fluids (e.g. `minecraft:lava` or modded fluids by pattern) into
+/// connected bodies and list their volume, source blocks, bounds and surface.
+/// `--min-volume` drops smaller bodies (2 blocks by default).
+pub fn run(args: Args) -> io::Result<()> {
+    let mut stdout = io::stdout();
+
+    let search = Search::parse(&args, 2)?;
+    let origin = &search.origin;
+    let search_fluids = args.positional(1, "fluids_to_find")?;
+    let min_volume = parse_option(&args, "min-volume")?.unwrap_or(2);
+
+    writeln!(stdout, "Looking for {}", search_fluids)?;
+
+    let blocks = locate_fluids(
+        &search.path,
+        search.area.as_ref(),
+        &BlockPatterns::parse(search_fluids),
+    )?;
+
+    let bodies = find_fluid_bodies(blocks)
+        .into_iter()
+        .filter(|body| body.volume >= min_volume)
+        .sorted_by(|a, b| {
+            origin
+                .distance_to(&a.center)
+                .total_cmp(&origin.distance_to(&b.center))
+                .then_with(|| b.volume.cmp(&a.volume))
+        })
+        .collect_vec();
+
+    writeln!(
+        stdout,
+        "{:<30} {:>7} {:>10} {:>10} {:>10}",
+        "fluid", "bodies", "blocks", "sources", "flowing"
+    )?;
+
+    let by_fluid = bodies.iter().into_group_map_by(|body| body.fluid.as_str());
+
+    for (fluid, bodies) in by_fluid.iter().sorted_by_key(|(fluid, _)| **fluid) {
+        let volume = bodies.iter().map(|body| body.volume).sum::<usize>();
+        let sources = bodies.iter().map(|body| body.sources).sum::<usize>();
+
+        writeln!(
+            stdout,
+            "{:<30} {:>7} {:>10} {:>10} {:>10}",
+            fluid,
+            bodies.len(),
+            volume,
+            sources,
+            volume - sources
+        )?;
+    }
+
+    for (num, body) in bodies.iter().enumerate() {
+        writeln!(
+            stdout,
+            "Body {} of {} ({} blocks, {} sources, {} flowing, {:.1}m away): {}, y {} to {}, surface {} faces, {} open at the top",
+            num,
+            body.fluid,
+            body.volume,
+            body.sources,
+            body.volume - body.sources,
+            origin.distance_to(&body.center),
+            body.bounds,
+            body.bounds.min.y,
+            body.bounds.max.y,
+            body.surface,
+            body.open_top
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use std::io::{self, Write};
+
+use itertools::Itertools;
+use wildmatch::WildMatch;
+
+use crate::{
+    cli::{invalid_input, parse_nbt_predicates, Args, Search},
+    scanner::{
+        blocks::BlockPatterns,
+        containers::{find_containers, ContainerKind},
+        items::{count_items, find_items, Item, ItemQuery},
+    },
+};
+
+/// `items <path_to_dimension> <items_to_find> [origin] [threshold]`
+///
+/// Find items inside containers, item frames, armor stands, minecarts and player
+/// inventories, including items inside shulker boxes. `--name` matches the custom
+/// name (ignoring case) and `--enchantment id[=level]` an enchantment of at least
+/// that level. `--nbt` predicates apply to the NBT of the container (or player).
+pub fn run(args: Args) -> io::Result<()> {
+    let mut stdout = io::stdout();
+
+    let search = Search::parse(&args, 2)?;
+    let origin = &search.origin;
+    let search_items = args.positional(1, "items_to_find")?;
+
+    let query = ItemQuery {
+        ids: BlockPatterns::parse(search_items),
+        name: args
+            .option("name")
+            .map(|name| WildMatch::new(&name.to_lowercase())),
+        enchantment: args
+            .option("enchantment")
+            .map(parse_enchantment)
+            .transpose()?,
+    };
+
+    writeln!(stdout, "Looking for {}", search_items)?;
+
+    let predicates = parse_nbt_predicates(&args)?;
+    let containers = find_containers(&search.path, search.area.as_ref())?
+        .into_iter()
+        .filter(|container| {
+            predicates
+                .iter()
+                .all(|predicate| predicate.matches(&container.nbt))
+        })
+        .collect_vec();
+    let mut found = containers
+        .iter()
+        .map(|container| (container, find_items(&container.items, &query)))
+        .filter(|(_, found)| !found.is_empty())
+        .collect_vec();
+
+    found.sort_by(|a, b| {
+        origin
+            .distance_to(&a.0.position)
+            .total_cmp(&origin.distance_to(&b.0.position))
+    });
+
+    let all = found.iter().flat_map(|(_, found)| found.iter());
+    let total = all
+        .clone()
+        .map(|found| found.item.count() as i64)
+        .sum::<i64>();
+
+    writeln!(
+        stdout,
+        "Found {} items in {} of {} containers",
+        total,
+        found.len(),
+        containers.len()
+    )?;
+
+    for (container, items) in &found {
+        let position = &container.position;
+        let holder = match container.kind {
+            ContainerKind::Block | ContainerKind::Entity => container.id.clone(),
+            ContainerKind::Player => format!("player {}", container.id),
+            ContainerKind::EnderChest => format!("ender chest of player {}", container.id),
+        };
+
+        writeln!(
+            stdout,
+            "{} at {}, {}, {} ({:.1}m away):",
+            holder,
+            position.x,
+            position.y,
+            position.z,
+            origin.distance_to(position)
+        )?;
+
+        for found in items {
+            let within = found
+                .within
+                .iter()
+                .map(|item| format!(" in {}", describe(item)))
+                .join("");
+
+            writeln!(
+                stdout,
+                "    {} {}{}",
+                found.item.count(),
+                describe(found.item),
+                within
+            )?;
+        }
+    }
+
+    let counts = count_items(all);
+
+    if counts.len() > 1 {
+        writeln!(stdout, "Totals:")?;
+
+        for (id, count) in counts.iter().sorted() {
+            writeln!(stdout, "  {}: {}", id, count)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// `id` or `id=level`, where the level is the lowest that matches
+pub fn parse_enchantment(value: &str) -> io::Result<(WildMatch, i32)> {
+    let (id, level) = match value.split_once('=') {
+        Some((id, level)) => (
+            id,
+            level
+                .parse()
+                .map_err(|err| invalid_input(format!("--enchantment: {}", err)))?,
+        ),
+        None => (value, 1),
+    };
+
+    Ok((WildMatch::new(id), level))
+}
+
+/// An item's id followed by its custom name and enchantments
+pub fn describe(item: &Item) -> String {
+    let name = item
+        .custom_name()
+        .map_or(String::new(), |name| format!(" \"{}\"", name));
+    let enchantments = item.enchantments();
+
+    match enchantments.is_empty() {
+        true => format!("{}{}", item.id, name),
+        false => format!(
+            "{}{} [{}]",
+            item.id,
+            name,
+            enchantments
+                .iter()
+                .map(|(id, level)| format!("{} {}", id, level))
+                .join(", ")
+        ),
+    }
+}
@@ -0,0 +1,83 @@
+use std::io::{self, Write};
+
+use itertools::Itertools;
+use wildmatch::WildMatch;
+
+use crate::{
+    cli::{parse_nbt_predicates, Args, Search},
+    scanner::{
+        loot::{find_loot, matches_table},
+        points::Point,
+    },
+};
+
+/// `loot <path_to_dimension> [origin] [threshold]`
+///
+/// List the structure loot containers and minecarts nobody has opened yet, grouped
+/// by loot table.
+/// `--table` only keeps tables matching a pattern, with or without the namespace,
+/// and `--nbt` the containers whose NBT matches.
+pub fn run(args: Args) -> io::Result<()> {
+    let mut stdout = io::stdout();
+
+    let search = Search::parse(&args, 1)?;
+    let origin = &search.origin;
+    let table = args.option("table").map(WildMatch::new);
+    let predicates = parse_nbt_predicates(&args)?;
+
+    let unopened = find_loot(&search.path, search.area.as_ref())?
+        .into_iter()
+        .filter(|container| {
+            table
+                .as_ref()
+                .is_none_or(|table| matches_table(table, &container.table))
+        })
+        .filter(|container| {
+            predicates
+                .iter()
+                .all(|predicate| predicate.matches(&container.nbt))
+        })
+        .map(|container| {
+            let distance = origin.distance_to(&container.position);
+            (container, distance)
+        })
+        .sorted_by(|a, b| a.1.total_cmp(&b.1))
+        .collect_vec();
+
+    writeln!(stdout, "Found {} unopened loot containers", unopened.len())?;
+
+    // Tables are listed by their closest container since everything is sorted by distance
+    let tables = unopened
+        .iter()
+        .map(|(container, _)| &container.table)
+        .unique()
+        .collect_vec();
+
+    for table in tables {
+        let containers = unopened
+            .iter()
+            .filter(|(container, _)| &container.table == table)
+            .collect_vec();
+
+        writeln!(stdout, "{} ({} containers):", table, containers.len())?;
+
+        for (container, distance) in containers {
+            let Point { x, y, z } = container.position;
+
+            writeln!(
+                stdout,
+                "    {} at {}, {}, {} ({:.1}m away){}",
+                container.id,
+                x,
+                y,
+                z,
+                distance,
+                container
+                    .seed
+                    .map_or(String::new(), |seed| format!(", seed {}", seed))
+            )?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,12 @@
+pub mod afk;
+pub mod branch;
+pub mod census;
+pub mod compare;
+pub mod entities;
+pub mod fluids;
+pub mod items;
+pub mod loot;
+pub mod portals;
+pub mod route;
+pub mod spawners;
+pub mod template;
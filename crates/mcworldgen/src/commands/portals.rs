@@ -0,0 +1,169 @@
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::{
+    cli::{Args, Search},
+    scanner::{
+        bounds::Bounds,
+        distance::{Origin, Scale},
+        points::Point,
+        portals::{find_portal_blocks, group_portals, link_portals, scan_margin, search_radius},
+    },
+};
+
+/// `portals <path_to_overworld> <path_to_nether> [origin] [threshold]`
+///
+/// Work out which portal each nether portal in the search area actually leads to,
+/// flagging pairs that don't lead back to each other and portals that would make
+/// the game generate a new one.
+pub fn run(args: Args) -> io::Result<()> {
+    let mut stdout = io::stdout();
+
+    let search = Search::parse(&args, 2)?;
+    let nether_path = PathBuf::from(args.positional(1, "path_to_nether")?);
+    let origin = &search.origin;
+
+    // Portals outside the area can still be linked to or lead back, so the scan
+    // reaches as far as those searches can
+    let area = search.area.bounds().columns();
+    let nether_area = Bounds {
+        min: area.min.rescale(Scale::Overworld, Scale::Nether),
+        max: area.max.rescale(Scale::Overworld, Scale::Nether),
+    }
+    .columns();
+
+    let overworld_margin = scan_margin(Scale::Overworld);
+    let nether_margin = scan_margin(Scale::Nether);
+    let overworld = group_portals(find_portal_blocks(
+        &search.path,
+        &area.expand(&Point {
+            x: overworld_margin,
+            y: 0,
+            z: overworld_margin,
+        }),
+    )?);
+    let nether = group_portals(find_portal_blocks(
+        &nether_path,
+        &nether_area.expand(&Point {
+            x: nether_margin,
+            y: 0,
+            z: nether_margin,
+        }),
+    )?);
+
+    let there = link_portals(&overworld, Scale::Overworld, &nether, Scale::Nether);
+    let back = link_portals(&nether, Scale::Nether, &overworld, Scale::Overworld);
+
+    writeln!(
+        stdout,
+        "Found {} Overworld portals and {} Nether portals",
+        overworld.len(),
+        nether.len()
+    )?;
+
+    let nether_origin = Origin {
+        point: origin.point.clone(),
+        scale: origin.scale,
+        dimension: Scale::Nether,
+        metric: origin.metric,
+    };
+
+    let mut mislinked = 0;
+    let mut generated = 0;
+
+    for (from, to, links, returns, origin, area, to_scale) in [
+        (
+            &overworld,
+            &nether,
+            &there,
+            &back,
+            origin,
+            &area,
+            Scale::Nether,
+        ),
+        (
+            &nether,
+            &overworld,
+            &back,
+            &there,
+            &nether_origin,
+            &nether_area,
+            Scale::Overworld,
+        ),
+    ] {
+        let from_name = dimension_name(origin.dimension);
+        let to_name = dimension_name(to_scale);
+
+        for (index, (portal, link)) in from.iter().zip(links.iter()).enumerate() {
+            if !area.contains(&portal.entry()) {
+                continue;
+            }
+
+            writeln!(
+                stdout,
+                "{} portal at {} ({} blocks, {:.1}m away)",
+                from_name,
+                portal.bounds,
+                portal.blocks.len(),
+                origin.distance_to(&portal.entry())
+            )?;
+
+            let Some((linked, arrival)) = &link.destination else {
+                generated += 1;
+                writeln!(
+                    stdout,
+                    "    no {} portal within {} blocks of {}, {}, {}: a new portal would be generated",
+                    to_name,
+                    search_radius(to_scale),
+                    link.target.x,
+                    link.target.y,
+                    link.target.z
+                )?;
+                continue;
+            };
+
+            writeln!(
+                stdout,
+                "    leads to the {} portal at {}, arriving at {}, {}, {}",
+                to_name, to[*linked].bounds, arrival.x, arrival.y, arrival.z
+            )?;
+
+            match &returns[*linked].destination {
+                Some((back, _)) if *back == index => {
+                    writeln!(stdout, "    which leads back here")?;
+                }
+                Some((back, _)) => {
+                    mislinked += 1;
+                    writeln!(
+                        stdout,
+                        "    mislinked: it leads back to the {} portal at {}",
+                        from_name, from[*back].bounds
+                    )?;
+                }
+                None => {
+                    mislinked += 1;
+                    writeln!(
+                        stdout,
+                        "    mislinked: going back would generate a new {} portal",
+                        from_name
+                    )?;
+                }
+            }
+        }
+    }
+
+    writeln!(
+        stdout,
+        "{} portals are mislinked and {} would generate a new portal",
+        mislinked, generated
+    )?;
+
+    Ok(())
+}
+
+fn dimension_name(scale: Scale) -> &'static str {
+    match scale {
+        Scale::Overworld => "Overworld",
+        Scale::Nether => "Nether",
+    }
+}
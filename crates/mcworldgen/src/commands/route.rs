@@ -0,0 +1,111 @@
+use std::io::{self, Write};
+
+use itertools::Itertools;
+
+use crate::{
+    cli::{parse_groups, parse_linking, parse_option, Args, Search},
+    scanner::{blocks::BlockPatterns, locate::locate, route::plan_route, veins::find_veins},
+};
+
+/// `route <path_to_dimension> <blocks_to_find> [origin] [threshold]`
+///
+/// Plan a path from the origin that visits the veins worth mining.
+/// Distances are measured in the scanned dimension.
+pub fn run(args: Args) -> io::Result<()> {
+    let mut stdout = io::stdout();
+
+    let search = Search::parse(&args, 2)?;
+    let patterns = BlockPatterns::parse(args.positional(1, "blocks_to_find")?);
+    let linking = parse_linking(&args)?;
+    let groups = parse_groups(&args)?;
+
+    let min_blocks = parse_option(&args, "min-blocks")?.unwrap_or(2);
+    let max_distance = parse_option::<f64>(&args, "max-distance")?;
+    let budget = parse_option::<f64>(&args, "budget")?;
+    let round_trip = args.flag("round-trip");
+
+    let summary = locate(&search.path, search.area.as_ref(), &patterns)?
+        .replacing_blocks(|blocks| groups.assign(blocks));
+
+    let start = search.origin.local();
+    let metric = search.origin.metric;
+
+    let veins = find_veins(summary.found, linking)
+        .filter(|vein| vein.blocks.len() >= min_blocks)
+        .filter(|vein| max_distance.is_none_or(|max| metric.distance(&start, &vein.center) <= max))
+        .collect_vec();
+
+    let stops = veins.iter().map(|vein| vein.center.clone()).collect_vec();
+    let order = plan_route(&start, &stops, metric, round_trip);
+
+    // Stop at the last vein that still leaves enough budget to get back (if needed)
+    let mut waypoints = vec![];
+    let mut travelled = 0.0;
+    let mut current = &start;
+
+    for index in order {
+        let leg = metric.distance(current, &stops[index]);
+        let home = match round_trip {
+            true => metric.distance(&stops[index], &start),
+            false => 0.0,
+        };
+
+        if budget.is_some_and(|budget| travelled + leg + home > budget) {
+            break;
+        }
+
+        travelled += leg;
+        current = &stops[index];
+        waypoints.push((index, leg, travelled));
+    }
+
+    writeln!(
+        stdout,
+        "Route through {} of {} veins from ({}, {}, {})",
+        waypoints.len(),
+        veins.len(),
+        start.x,
+        start.y,
+        start.z
+    )?;
+
+    let mut collected = 0;
+
+    for (num, (index, leg, total)) in waypoints.iter().enumerate() {
+        let vein = &veins[*index];
+        collected += vein.blocks.len();
+
+        writeln!(
+            stdout,
+            "{:>4}. ({}, {}, {}) {} ({} blocks), leg {:.1}m, total {:.1}m, ore {}",
+            num + 1,
+            vein.center.x,
+            vein.center.y,
+            vein.center.z,
+            vein.blocks[0].group,
+            vein.blocks.len(),
+            leg,
+            total,
+            collected
+        )?;
+    }
+
+    if round_trip {
+        let leg = metric.distance(current, &start);
+        travelled += leg;
+
+        writeln!(
+            stdout,
+            "      back to ({}, {}, {}), leg {:.1}m, total {:.1}m",
+            start.x, start.y, start.z, leg, travelled
+        )?;
+    }
+
+    writeln!(
+        stdout,
+        "Collected {} blocks over {:.1}m",
+        collected, travelled
+    )?;
+
+    Ok(())
+}
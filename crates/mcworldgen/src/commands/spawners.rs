@@ -0,0 +1,214 @@
+use std::io::{self, Write};
+
+use itertools::Itertools;
+
+use crate::{
+    cli::{parse_nbt_predicates, Args, Search},
+    scanner::{
+        blocks::{BlockEntity, BlockPatterns, LightLimit, SpawnRules},
+        locate::locate,
+        lookup::BlockLookup,
+        structures::StructureLookup,
+        viability::check_viability,
+    },
+};
+
+/// `spawners <path_to_dimension> [origin] [threshold]`
+///
+/// List every mob spawner in the search area with the mobs it spawns, its settings
+/// and the structure it's part of. `--mob` only keeps spawners that can spawn a mob
+/// and `--nbt` those whose NBT matches.
+pub fn run(args: Args) -> io::Result<()> {
+    let mut stdout = io::stdout();
+
+    let search = Search::parse(&args, 1)?;
+    let origin = &search.origin;
+
+    let spawners = find_spawners(&args, &search)?
+        .into_iter()
+        .map(|entity| {
+            let distance = origin.distance_to(&entity.pos());
+            (entity, distance)
+        })
+        .sorted_by(|a, b| a.1.total_cmp(&b.1))
+        .collect_vec();
+
+    writeln!(stdout, "Found {} spawners", spawners.len())?;
+
+    let mut structures = StructureLookup::new(&search.path);
+    let mut blocks = args
+        .flag("viability")
+        .then(|| BlockLookup::new(&search.path));
+
+    for (entity, distance) in &spawners {
+        let mobs = entity.mobs();
+        let inside = structures.structures_at(&entity.pos());
+
+        writeln!(
+            stdout,
+            "Spawner at {}, {}, {} ({:.1}m away): {}{}",
+            entity.x,
+            entity.y,
+            entity.z,
+            distance,
+            mobs.first().copied().unwrap_or("unknown mob"),
+            match inside.is_empty() {
+                true => String::new(),
+                false => format!(" in {}", inside.join(", ")),
+            }
+        )?;
+
+        write_spawner(&mut stdout, entity)?;
+
+        if let Some(blocks) = &mut blocks {
+            let viability = check_viability(entity, blocks);
+
+            writeln!(
+                stdout,
+                "    viability: {:.0}% ({} of {} positions; {} blocked, {} without a floor, {} too bright or dark{})",
+                viability.score() * 100.0,
+                viability.valid,
+                viability.positions,
+                viability.blocked,
+                viability.no_floor,
+                viability.wrong_light,
+                match viability.unknown_light {
+                    0 => String::new(),
+                    unknown => format!(", light unknown for {}", unknown),
+                }
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The spawners in the search area that can spawn one of the `--mob` patterns and
+/// match the `--nbt` predicates (if given)
+pub fn find_spawners(args: &Args, search: &Search) -> io::Result<Vec<BlockEntity>> {
+    let mob = args.option("mob").map(BlockPatterns::parse);
+    let predicates = parse_nbt_predicates(args)?;
+
+    let summary = locate(
+        &search.path,
+        search.area.as_ref(),
+        &BlockPatterns::parse("minecraft:mob_spawner"),
+    )?;
+
+    Ok(summary
+        .found
+        .into_iter()
+        .filter_map(|block| block.enity)
+        .filter(|entity| {
+            mob.as_ref()
+                .is_none_or(|mob| entity.mobs().iter().any(|id| mob.matches(id)))
+        })
+        .filter(|entity| {
+            predicates
+                .iter()
+                .all(|predicate| predicate.matches(&entity.nbt))
+        })
+        .collect())
+}
+
+/// Print a spawner's settings, skipping the ones it doesn't have
+pub fn write_spawner(out: &mut impl Write, entity: &BlockEntity) -> io::Result<()> {
+    let potentials = entity.spawn_potentials.as_deref().unwrap_or_default();
+    let total_weight = potentials
+        .iter()
+        .map(|potential| potential.weight.unwrap_or(1).max(0))
+        .sum::<i32>();
+
+    if !potentials.is_empty() {
+        let weighted = potentials
+            .iter()
+            .map(|potential| {
+                let mob = potential
+                    .data
+                    .as_ref()
+                    .and_then(|data| data.mob())
+                    .unwrap_or("unknown mob");
+                let weight = potential.weight.unwrap_or(1).max(0);
+
+                format!(
+                    "{} {:.0}% (weight {})",
+                    mob,
+                    weight as f64 / total_weight.max(1) as f64 * 100.0,
+                    weight
+                )
+            })
+            .join(", ");
+
+        writeln!(out, "    potentials: {}", weighted)?;
+    }
+
+    if let Some(delay) = entity.delay {
+        writeln!(out, "    next spawn: {} ticks", delay)?;
+    }
+
+    if let (Some(min), Some(max)) = (entity.min_spawn_delay, entity.max_spawn_delay) {
+        writeln!(out, "    spawn delay: {}-{} ticks", min, max)?;
+    }
+
+    match (entity.spawn_count, entity.spawn_range) {
+        (Some(count), Some(range)) => {
+            writeln!(out, "    spawn count: {} @ {} blocks", count, range)?
+        }
+        (Some(count), None) => writeln!(out, "    spawn count: {}", count)?,
+        (None, Some(range)) => writeln!(out, "    spawn range: {} blocks", range)?,
+        (None, None) => {}
+    }
+
+    if let Some(max) = entity.max_entities {
+        writeln!(out, "    max entities: {}", max)?;
+    }
+
+    if let Some(range) = entity.activation_range {
+        writeln!(out, "    activation range: {} blocks", range)?;
+    }
+
+    let rules = entity
+        .spawn_data
+        .iter()
+        .chain(
+            potentials
+                .iter()
+                .filter_map(|potential| potential.data.as_ref()),
+        )
+        .filter_map(|data| {
+            Some((
+                data.mob().unwrap_or("unknown mob"),
+                data.custom_spawn_rules.as_ref()?,
+            ))
+        })
+        .unique_by(|(mob, _)| *mob)
+        .collect_vec();
+
+    for (mob, rules) in rules {
+        writeln!(
+            out,
+            "    spawn rules for {}: {}",
+            mob,
+            describe_rules(rules)
+        )?;
+    }
+
+    Ok(())
+}
+
+fn describe_rules(rules: &SpawnRules) -> String {
+    let describe = |name: &str, limit: &Option<LightLimit>| {
+        limit.as_ref().map(|limit| match limit.range() {
+            (min, max) if min == max => format!("{} light {}", name, min),
+            (min, max) => format!("{} light {}-{}", name, min, max),
+        })
+    };
+
+    [
+        describe("block", &rules.block_light_limit),
+        describe("sky", &rules.sky_light_limit),
+    ]
+    .into_iter()
+    .flatten()
+    .join(", ")
+}
@@ -0,0 +1,97 @@
+use std::{
+    io::{self, Write},
+    path::Path,
+};
+
+use itertools::Itertools;
+
+use crate::{
+    cli::{invalid_input, Args, Search},
+    scanner::{
+        locate::locate,
+        template::{count_palettes, find_template, Template},
+    },
+};
+
+/// `template <path_to_dimension> <template> [origin] [threshold]`
+///
+/// Find every place an arrangement of blocks appears. The template is a `.nbt` or
+/// `.schem` file, or written inline. `--rotate` also tries it turned by 90, 180 and
+/// 270 degrees and `--mirror` also tries it mirrored.
+pub fn run(args: Args) -> io::Result<()> {
+    let mut stdout = io::stdout();
+
+    let search = Search::parse(&args, 2)?;
+    let origin = &search.origin;
+
+    let source = args.positional(1, "template")?;
+    let template = match source.ends_with(".nbt") || source.ends_with(".schem") {
+        true => Template::read(Path::new(source)),
+        false => Template::parse(source),
+    }
+    .map_err(|err| invalid_input(err.to_string()))?;
+
+    let orientations = template.orientations(args.flag("rotate"), args.flag("mirror"));
+    let anchors = template
+        .anchors()
+        .map_err(|err| invalid_input(err.to_string()))?;
+    let size = template.size();
+
+    writeln!(
+        stdout,
+        "Looking for a {}x{}x{} template of {} blocks in {} orientations",
+        size.x,
+        size.y,
+        size.z,
+        template.cells.len(),
+        orientations.len()
+    )?;
+
+    // Only the places holding the anchor block can be where the template starts, so
+    // the rarest block keeps the number of places to check down
+    let area = search.area.as_ref();
+    let counts = count_palettes(
+        &search.path,
+        area,
+        &anchors.iter().map(|cell| &cell.blocks).collect_vec(),
+    )?;
+    let (anchor, _) = anchors
+        .into_iter()
+        .zip(counts)
+        .min_by_key(|(_, count)| *count)
+        .unwrap();
+
+    let anchors = locate(&search.path, area, &anchor.blocks)?
+        .found
+        .into_iter()
+        .filter(|block| block.enity.is_none())
+        .map(|block| block.pos())
+        .collect_vec();
+
+    let found = find_template(&search.path, anchor, &orientations, anchors)
+        .into_iter()
+        .map(|found| {
+            let distance = origin.distance_to(&found.bounds.min);
+            (found, distance)
+        })
+        .sorted_by(|a, b| a.1.total_cmp(&b.1))
+        .collect_vec();
+
+    writeln!(stdout, "Found {} matches", found.len())?;
+
+    for (found, distance) in found {
+        writeln!(
+            stdout,
+            "Match at {} ({:.1}m away, turned {}°{})",
+            found.bounds,
+            distance,
+            found.degrees,
+            match found.mirrored {
+                true => ", mirrored",
+                false => "",
+            }
+        )?;
+    }
+
+    Ok(())
+}
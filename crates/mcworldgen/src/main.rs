@@ -1,23 +1,28 @@
+mod cli;
+mod commands;
 mod scanner;
 
-use fastnbt::from_bytes;
 use itertools::Itertools;
-use scanner::blocks::{BlockDescriptor, BlockEntity};
-use serde::Deserialize;
+use scanner::blocks::{count_variants, BlockPatterns};
 use std::{
     cmp::Ordering,
-    collections::HashSet,
+    collections::HashMap,
     env,
-    fs::File,
     io::{self, Write},
-    path::PathBuf,
+    path::Path,
 };
 
-use fastanvil::*;
-use rayon::prelude::{IntoParallelIterator, ParallelIterator};
-use wildmatch::WildMatch;
-
-use crate::scanner::{bounds::Bounds, points::Point, scan::ScanResult, veins::find_veins};
+use crate::cli::{parse_groups, parse_linking, parse_nbt_predicates, parse_option, Args, Search};
+use crate::scanner::{
+    deposits::{find_deposits, ClusterBy},
+    exposure::{exposure_by_y, find_exposure, vein_exposure, Exposure},
+    histogram::{write_histograms, HistogramFormat, Normalization, YHistogram},
+    locate::{locate, locate_counting},
+    points::Point,
+    saved::save_scan,
+    vein_stats::VeinStats,
+    veins::{find_veins, Vein},
+};
 
 fn main() {
     _ = match try_main() {
@@ -28,76 +33,108 @@ fn main() {
 }
 
 fn try_main() -> std::result::Result<(), io::Error> {
-    let mut stdout = io::stdout();
-
-    let default_home = "0,8,0".to_owned();
-    let default_threshold = "100,8,100".to_owned();
-
-    let args = env::args().collect::<Vec<String>>();
-
-    let dim_path = PathBuf::from(args[1].clone());
-    let search_block = args[2].clone();
-    let home_str = args
-        .get(3)
-        .unwrap_or(&default_home)
-        .split(',')
-        .collect_vec(); // .unwrap_or(&"0,0,0".to_owned()).split(",").collect_vec();
-    let threshold_str = args
-        .get(4)
-        .unwrap_or(&default_threshold)
-        .split(',')
-        .collect_vec();
-
-    let home = Point {
-        x: home_str[0].parse().unwrap(),
-        y: home_str[1].parse().unwrap(),
-        z: home_str[2].parse().unwrap(),
-    };
-
-    let threshold = Point {
-        x: threshold_str[0].parse().unwrap(),
-        y: threshold_str[1].parse().unwrap(),
-        z: threshold_str[2].parse().unwrap(),
-    };
+    let args = Args::parse(env::args().skip(1));
+
+    match args.positional.first().map(String::as_str) {
+        Some("afk") => commands::afk::run(args.shift()),
+        Some("branch") => commands::branch::run(args.shift()),
+        Some("census") => commands::census::run(args.shift()),
+        Some("compare") => commands::compare::run(args.shift()),
+        Some("entities") => commands::entities::run(args.shift()),
+        Some("fluids") => commands::fluids::run(args.shift()),
+        Some("items") => commands::items::run(args.shift()),
+        Some("loot") => commands::loot::run(args.shift()),
+        Some("portals") => commands::portals::run(args.shift()),
+        Some("route") => commands::route::run(args.shift()),
+        Some("spawners") => commands::spawners::run(args.shift()),
+        Some("template") => commands::template::run(args.shift()),
+        _ => scan(args),
+    }
+}
 
-    let boundary = Bounds::from_point(&home).expand(&threshold);
+/// `<path_to_dimension> <blocks_to_find> [origin] [threshold]`
+///
+/// Find blocks matching a pattern and group them into veins.
+fn scan(args: Args) -> std::result::Result<(), io::Error> {
+    let mut stdout = io::stdout();
 
-    let block_matcher = WildMatch::new(&search_block);
+    let search = Search::parse(&args, 2)?;
+    let origin = &search.origin;
+    let boundary = &search.area;
+
+    let search_block = args.positional(1, "blocks_to_find")?.to_string();
+    let patterns = BlockPatterns::parse(&search_block);
+    let linking = parse_linking(&args)?;
+    let groups = parse_groups(&args)?;
+    let deposits = parse_option::<ClusterBy>(&args, "deposits")?;
+    let eps = parse_option(&args, "eps")?.unwrap_or(16.0);
+    let min_points = parse_option(&args, "min-points")?.unwrap_or(3);
+    let predicates = parse_nbt_predicates(&args)?;
     writeln!(stdout, "Looking for {}", search_block)?;
 
-    let loader = RegionFileLoader::new(dim_path.join("region"));
-    let regions = loader.list().unwrap();
-    let regions_len = regions.len();
-    writeln!(stdout, "Scanning {} regions", regions_len)?;
+    let histogram = parse_option::<HistogramFormat>(&args, "histogram")?;
+    let per = parse_option(&args, "per")?.unwrap_or(Normalization::Chunk);
 
-    // Search for the specified block in each region
-    let results = regions
-        .into_par_iter()
-        .map(|(rx, rz)| {
-            let mut file = loader.region(rx, rz).unwrap().unwrap();
+    // Counting the blocks of sections without a match means unpacking them too
+    let count_all = histogram.is_some() && per == Normalization::Blocks;
+    let summary = match count_all {
+        true => locate_counting(&search.path, boundary.as_ref(), &patterns)?,
+        false => locate(&search.path, boundary.as_ref(), &patterns)?,
+    };
+    let mut summary = summary.replacing_blocks(|blocks| groups.assign(blocks));
+
+    // Only block entities carry NBT, so plain blocks never match a predicate
+    if !predicates.is_empty() {
+        summary = summary.filtering_blocks(|block| {
+            block.enity.as_ref().is_some_and(|entity| {
+                predicates
+                    .iter()
+                    .all(|predicate| predicate.matches(&entity.nbt))
+            })
+        });
+    }
 
+    if let Some(path) = args.option("save") {
+        save_scan(Path::new(path), &summary)?;
+    }
 
-            locate_in_region(block_matcher.clone(), &mut file, rx.0 as i64, rz.0 as i64)
-        })
-        .filter_map(Option::Some)
-        .collect::<Vec<ScanResult>>();
-
-    // Summarize the results
-    let summary = ScanResult::combine(results.into_iter()).replacing_blocks(|blocks| {
-        blocks
-            .into_iter()
-            .filter(|block| boundary.contains(&block.pos()))
-            .sorted()
-    });
+    writeln!(
+        stdout,
+        "Scanned {} regions ({} skipped)",
+        summary.regions, summary.skipped_regions
+    )?;
+    writeln!(
+        stdout,
+        "Scanned {} chunks ({} skipped)",
+        summary.chunks, summary.skipped_chunks
+    )?;
+    writeln!(
+        stdout,
+        "Scanned {} sections ({} skipped, {} without a match)",
+        summary.sections, summary.skipped_sections, summary.unmatched_sections
+    )?;
+    match count_all {
+        true => writeln!(stdout, "Scanned {} blocks", summary.blocks)?,
+        false => writeln!(
+            stdout,
+            "Scanned {} blocks in sections with a match",
+            summary.blocks
+        )?,
+    }
+    writeln!(stdout, "Types:")?;
 
-    let all_block_types =
-        HashSet::<String>::from_iter(summary.found.iter().map(|b| b.name.clone()));
+    for (group, variants) in count_variants(summary.found.iter()) {
+        writeln!(
+            stdout,
+            "  {} ({} blocks)",
+            group,
+            variants.values().sum::<usize>()
+        )?;
 
-    writeln!(stdout, "Scanned {} regions", summary.regions)?;
-    writeln!(stdout, "Scanned {} chunks", summary.chunks)?;
-    writeln!(stdout, "Scanned {} sections", summary.sections)?;
-    writeln!(stdout, "Scanned {} blocks", summary.blocks)?;
-    writeln!(stdout, "Types: {}", all_block_types.into_iter().join(", "))?;
+        for (variant, count) in variants {
+            writeln!(stdout, "    {}: {}", variant, count)?;
+        }
+    }
 
     writeln!(
         stdout,
@@ -106,232 +143,211 @@ fn try_main() -> std::result::Result<(), io::Error> {
         search_block
     )?;
 
+    let exposure = match args.flag("exposure") {
+        true => Some(find_exposure(&search.path, &summary.found)),
+        false => None,
+    };
+
+    if let Some(exposure) = &exposure {
+        write_exposure(&mut stdout, exposure)?;
+    }
+
+    if let Some(format) = histogram {
+        let histograms = patterns
+            .0
+            .iter()
+            .map(|(pattern, matcher)| {
+                YHistogram::from_blocks(
+                    pattern,
+                    summary
+                        .found
+                        .iter()
+                        .filter(|block| matcher.matches(&block.name)),
+                )
+            })
+            .collect_vec();
+
+        write_histograms(&mut stdout, &histograms, &summary, per, format)?;
+    }
+
     // Match blocks to their entities
 
     for desc in &summary.found {
-        let block_pos = Point {
-            x: desc.x,
-            y: desc.y,
-            z: desc.z,
-        };
-        let _distance = block_pos.distance_to(&home);
+        let distance = origin.distance_to(&desc.pos());
 
         // writeln!(stdout, "  {} at {}, {}, {} -> {} blocks away", desc.name, desc.x, desc.y, desc.z, distance)?;
 
+        if let Some(exposure) = exposure.as_ref().and_then(|e| e.get(&desc.pos())) {
+            writeln!(
+                stdout,
+                "  {} at {}, {}, {} ({:.1}m away): {} exposed faces ({} air, {} water, {} lava)",
+                desc.name,
+                desc.x,
+                desc.y,
+                desc.z,
+                distance,
+                exposure.faces(),
+                exposure.air,
+                exposure.water,
+                exposure.lava
+            )?;
+        }
+
         if let Some(entity) = &desc.enity {
             if entity.id != "minecraft:mob_spawner" {
                 continue;
             }
 
+            commands::spawners::write_spawner(&mut stdout, entity)?;
+        }
+    }
+
+    // writeln!(stdout, "Looking for veins")?;
+    let veins = find_veins(summary.found, linking).collect_vec();
+
+    if args.flag("vein-stats") {
+        write_vein_stats(&mut stdout, &veins, summary.chunks)?;
+    }
+
+    if let Some(by) = deposits {
+        for (num, deposit) in find_deposits(&veins, by, eps, min_points)
+            .iter()
+            .enumerate()
+        {
+            let center = &deposit.center;
+
             writeln!(
                 stdout,
-                "    spawn delay: {}-{} ticks",
-                entity.min_spawn_delay.unwrap(),
-                entity.max_spawn_delay.unwrap()
-            )?;
-            writeln!(
-                stdout,
-                "    spawn count: {} @ {} blocks",
-                entity.spawn_count.unwrap(),
-                entity.spawn_range.unwrap()
-            )?;
-            writeln!(stdout, "    max entities: {}", entity.max_entities.unwrap())?;
-            writeln!(
-                stdout,
-                "    activation range: {} blocks",
-                entity.activation_range.unwrap()
+                "Deposit {} ({} blocks in {} veins, {:.1}m away): {} around ({}, {}, {})",
+                num,
+                deposit.blocks,
+                deposit.veins,
+                origin.distance_to(center),
+                deposit.bounds,
+                center.x,
+                center.y,
+                center.z
             )?;
         }
     }
 
-    // writeln!(stdout, "Looking for veins")?;
-    let veins = find_veins(summary.found);
-
     for (num, vein) in veins
+        .into_iter()
+        .filter(|vein| vein.blocks.len() > 1)
         .sorted_by(|a, b| {
-            let a_dist = a.center.distance_to(&home);
-            let b_dist = b.center.distance_to(&home);
+            let a_dist = origin.distance_to(&a.center);
+            let b_dist = origin.distance_to(&b.center);
             let a_count = a.blocks.len();
             let b_count = b.blocks.len();
 
             Ordering::Equal
-                .then_with(|| a_dist.cmp(&b_dist))
+                .then_with(|| a_dist.total_cmp(&b_dist))
                 .then_with(|| b_count.cmp(&a_count))
         })
         .enumerate()
     {
+        let members = vein.members();
+        let mix = match members.len() {
+            1 => String::new(),
+            _ => format!(
+                " [{}]",
+                members
+                    .iter()
+                    .map(|(name, count)| format!("{} {}", name, count))
+                    .join(", ")
+            ),
+        };
+
+        let exposed = match &exposure {
+            Some(exposure) => {
+                let (blocks, faces) = vein_exposure(&vein, exposure);
+                format!(", {} exposed ({} faces)", blocks, faces.faces())
+            }
+            None => String::new(),
+        };
+
         writeln!(
             stdout,
-            "Vein {} ({} blocks, {}m away): {}",
+            "Vein {} ({} blocks{}, {:.1}m away): {}{}",
             num,
             vein.blocks.len(),
-            vein.center.distance_to(&home),
-            vein.bounds
+            exposed,
+            origin.distance_to(&vein.center),
+            vein.bounds,
+            mix
         )?;
     }
 
     Ok(())
 }
 
-/// A Minecraft chunk.
-#[derive(Deserialize, Debug)]
-pub struct ChunkEntityContainer {
-    #[serde(rename = "block_entities")]
-    pub block_entities: Vec<BlockEntity>,
-}
-
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
-pub struct SpawnData {
-    #[serde(rename = "entity")]
-    pub entity: SpawnEntity,
-}
-
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
-pub struct SpawnEntity {
-    #[serde(rename = "id")]
-    pub id: String,
-}
-
-fn locate_in_region(name: WildMatch, region: &mut Region<File>, rx: i64, rz: i64) -> ScanResult {
-    let chunk_coords = (0..32i64).flat_map(|z| (0..32i64).map(move |x| (x, z)));
-
-    let mut result = ScanResult {
-        regions: 1,
-        chunks: 0,
-        sections: 0,
-        blocks: 0,
-        found: vec![],
-    };
-
-    for (cx, cz) in chunk_coords {
-        let chunk = region.read_chunk(cx as usize, cz as usize);
-        let chunk = match chunk {
-            Ok(Some(data)) => Some(data),
-            _ => None,
-        };
-
-        let matching_entities = chunk
-            .clone()
-            .and_then(|data| from_bytes::<ChunkEntityContainer>(&data).ok())
-            .map_or(vec![], |container| container.block_entities)
-            .into_iter()
-            .filter(|entity| name.matches(&entity.id))
-            .map(move |entity| BlockDescriptor {
-                name: entity.id.clone(),
-                x: entity.x,
-                y: entity.y,
-                z: entity.z,
-                enity: Some(entity),
-            })
-            .collect_vec();
-
-        result.found.extend(matching_entities);
-
-        // let blaze_spawners = block_entities
-        //     .into_iter()
-        //     .filter(|entity| {
-        //         return match entity.spawn_data {
-        //             Some(ref spawn_data) => {
-        //                 match spawn_data.entity.id.as_str() {
-        //                     "minecraft:blaze" => true,
-        //                     _ => false,
-        //                 }
-        //             },
-        //             None => false,
-        //         }
-        //     })
-        //     .map(|entity| {
-        //         BlockDescriptor {
-        //             name: "minecraft:blaze_spawner".to_string(),
-        //             x: entity.x,
-        //             y: entity.y,
-        //             z: entity.z,
-        //             enity: Some(entity),
-        //         }
-        //     })
-        //     .collect_vec();
-
-        // if blaze_spawners.len() > 0 {
-        //     println!("blaze_spawners: {:?}", block_entities);
-        // }
-
-        // result.found.extend(blaze_spawners);
-
-        let chunk = chunk.and_then(|data| match JavaChunk::from_bytes(&data) {
-            Ok(JavaChunk::Post18(chunk)) => Some(chunk),
-            _ => None,
-        });
-
-        let tower = chunk.and_then(|chunk| chunk.sections);
-        if tower.is_none() {
-            continue;
-        }
-
-        let mut has_non_air = false;
-
-        let tower = tower.unwrap();
-        for section in tower.sections() {
-            let palette = section.block_states.palette();
-            let is_air = palette.len() == 1 && palette.get(0).unwrap().name() == "minecraft:air";
-            let iter = section.block_states.try_iter_indices();
-            let sy = (section.y() as i64) * 16;
-            has_non_air = has_non_air || !is_air;
-
-            if iter.is_none() {
-                continue;
-            }
-
-            // Skip air sections completely
-            if is_air {
-                continue;
-            }
-
-            result.sections += 1;
-
-            for (i, block_index) in iter.unwrap().enumerate() {
-                let block = palette.get(block_index).unwrap();
-
-                // Skip air blocks
-                if block.name() == "minecraft:air" {
-                    continue;
-                }
-
-                result.blocks += 1;
+fn write_exposure(out: &mut impl Write, exposure: &HashMap<Point, Exposure>) -> io::Result<()> {
+    let exposed = exposure.values().filter(|e| e.is_exposed()).count();
+    let (air, water, lava) = exposure.values().fold((0, 0, 0), |(air, water, lava), e| {
+        (air + e.air, water + e.water, lava + e.lava)
+    });
 
-                if !name.matches(block.name()) {
-                    continue;
-                }
+    writeln!(
+        out,
+        "Exposed: {} of {} blocks ({} faces touch air, {} water, {} lava)",
+        exposed,
+        exposure.len(),
+        air,
+        water,
+        lava
+    )?;
+    writeln!(
+        out,
+        "{:>6} {:>8} {:>8} {:>8}",
+        "y", "blocks", "exposed", "rate"
+    )?;
 
-                let x = (i & 0x000F) as i64;
-                let y = ((i & 0x0F00) >> 8) as i64;
-                let z = ((i & 0x00F0) >> 4) as i64;
+    for (y, level) in exposure_by_y(exposure).iter().rev() {
+        writeln!(
+            out,
+            "{:>6} {:>8} {:>8} {:>7.1}%",
+            y,
+            level.blocks,
+            level.exposed,
+            level.exposed as f64 / level.blocks as f64 * 100.0
+        )?;
+    }
 
-                // 512 blocks per region + 16 blocks per chunk + x
-                // Normalized
+    Ok(())
+}
 
-                result.found.push(BlockDescriptor {
-                    name: normalize_name(block.name()),
-                    x: rx * 512 + cx * 16 + x,
-                    y: sy + y,
-                    z: rz * 512 + cz * 16 + z,
-                    enity: None,
-                });
-            }
-        }
+fn write_vein_stats(out: &mut impl Write, veins: &[Vein], chunks: usize) -> io::Result<()> {
+    writeln!(
+        out,
+        "{:<40} {:>7} {:>9} {:>7} {:>5} {:>6} {:>5} {:>5} {:>8} {:>8}",
+        "vein stats",
+        "veins",
+        "per chunk",
+        "single",
+        "min",
+        "median",
+        "p90",
+        "max",
+        "volume",
+        "center y"
+    )?;
 
-        if has_non_air {
-            result.chunks += 1;
-        }
+    for stats in VeinStats::by_group(veins) {
+        writeln!(
+            out,
+            "{:<40} {:>7} {:>9.3} {:>6.1}% {:>5} {:>6} {:>5} {:>5} {:>8.1} {:>8.1}",
+            stats.group,
+            stats.veins,
+            stats.veins as f64 / chunks.max(1) as f64,
+            stats.single as f64 / stats.veins as f64 * 100.0,
+            stats.min_size,
+            stats.median_size,
+            stats.p90_size,
+            stats.max_size,
+            stats.mean_volume,
+            stats.mean_center_y
+        )?;
     }
 
-    result
-}
-
-fn normalize_name(name: &str) -> String {
-    name
-        .trim_end_matches("_stone")
-        .trim_end_matches("_kivi")
-        .trim_end_matches("_deepslate")
-        .replace("deepslate_", "")
-        .replace("aethersteel:aetherslate_", "minecraft:")
+    Ok(())
 }
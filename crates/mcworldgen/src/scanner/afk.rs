@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use super::{bounds::Bounds, points::Point};
+
+/// The range a spawner uses when `RequiredPlayerRange` is missing
+pub const DEFAULT_ACTIVATION_RANGE: i64 = 16;
+
+/// A place to stand that keeps a set of spawners active at once
+pub struct AfkSpot {
+    /// The block the player stands in
+    pub position: Point,
+
+    /// Indices of the spawners that are active from here
+    pub spawners: Vec<usize>,
+
+    /// How many positions activate exactly the same spawners
+    pub positions: usize,
+}
+
+/// Whether a player standing in a block activates a spawner
+///
+/// Spawners check the distance from their center to the player's feet.
+pub fn activates(player: &Point, spawner: &Point, range: i64) -> bool {
+    let dx = player.x as f64 + 0.5 - (spawner.x as f64 + 0.5);
+    let dy = player.y as f64 - (spawner.y as f64 + 0.5);
+    let dz = player.z as f64 + 0.5 - (spawner.z as f64 + 0.5);
+
+    dx * dx + dy * dy + dz * dz < (range * range) as f64
+}
+
+/// The largest activation range spots are searched for, which keeps the number of
+/// positions to check (up to `(2 * range + 1)^3` per overlap) in bounds
+pub const MAX_ACTIVATION_RANGE: i64 = 32;
+
+/// Find the spots that activate the most spawners together
+///
+/// Every position is grouped by the spawners it activates. For each group
+/// that isn't contained in a larger one, the most central position (the one
+/// furthest from leaving any spawner's range) is returned. Larger ranges than
+/// `MAX_ACTIVATION_RANGE` are cut down to it.
+pub fn find_afk_spots(spawners: &[(Point, i64)], min_spawners: usize) -> Vec<AfkSpot> {
+    let min_spawners = min_spawners.max(1);
+    let spawners = spawners
+        .iter()
+        .map(|(spawner, range)| (spawner.clone(), (*range).min(MAX_ACTIVATION_RANGE)))
+        .collect_vec();
+    let boxes = spawners
+        .iter()
+        .map(|(spawner, range)| {
+            Bounds::from_point(spawner).expand(&Point {
+                x: *range,
+                y: *range,
+                z: *range,
+            })
+        })
+        .collect_vec();
+
+    // Positions in a single spawner's range can only be spots on their own, so
+    // otherwise only the overlaps of the spawners' ranges are checked
+    let areas = match min_spawners {
+        1 => boxes.clone(),
+        _ => boxes
+            .iter()
+            .enumerate()
+            .flat_map(|(i, a)| boxes[i + 1..].iter().filter_map(|b| a.intersection(b)))
+            .collect_vec(),
+    };
+
+    let mut coverage = HashMap::<Point, Vec<usize>>::new();
+
+    for (index, area) in areas.iter().enumerate() {
+        for ((x, y), z) in (area.min.x..=area.max.x)
+            .cartesian_product(area.min.y..=area.max.y)
+            .cartesian_product(area.min.z..=area.max.z)
+        {
+            let player = Point { x, y, z };
+
+            // Overlaps overlap each other too, and were checked there already
+            if areas[..index].iter().any(|other| other.contains(&player)) {
+                continue;
+            }
+
+            let covered = spawners
+                .iter()
+                .enumerate()
+                .filter(|(i, (spawner, range))| {
+                    boxes[*i].contains(&player) && activates(&player, spawner, *range)
+                })
+                .map(|(i, _)| i)
+                .collect_vec();
+
+            if covered.len() >= min_spawners {
+                coverage.insert(player, covered);
+            }
+        }
+    }
+
+    let groups = coverage
+        .into_iter()
+        .into_group_map_by(|(_, covered)| covered.clone());
+
+    // The smallest distance from a position to the edge of any of the spawners' ranges
+    let slack = |position: &Point, covered: &[usize]| {
+        covered
+            .iter()
+            .map(|index| {
+                let (spawner, range) = &spawners[*index];
+                let dx = (position.x - spawner.x) as f64;
+                let dy = position.y as f64 - (spawner.y as f64 + 0.5);
+                let dz = (position.z - spawner.z) as f64;
+
+                *range as f64 - (dx * dx + dy * dy + dz * dz).sqrt()
+            })
+            .fold(f64::MAX, f64::min)
+    };
+
+    let spots = groups
+        .into_iter()
+        .map(|(covered, positions)| {
+            let count = positions.len();
+            let position = positions
+                .into_iter()
+                .map(|(position, _)| position)
+                .max_by(|a, b| {
+                    slack(a, &covered)
+                        .total_cmp(&slack(b, &covered))
+                        .then_with(|| (b.x, b.y, b.z).cmp(&(a.x, a.y, a.z)))
+                })
+                .unwrap();
+
+            AfkSpot {
+                position,
+                spawners: covered,
+                positions: count,
+            }
+        })
+        .collect_vec();
+
+    // Spots that activate a subset of another spot's spawners are never better
+    spots
+        .iter()
+        .filter(|spot| {
+            !spots.iter().any(|other| {
+                other.spawners.len() > spot.spawners.len()
+                    && spot.spawners.iter().all(|s| other.spawners.contains(s))
+            })
+        })
+        .map(|spot| AfkSpot {
+            position: spot.position.clone(),
+            spawners: spot.spawners.clone(),
+            positions: spot.positions,
+        })
+        .sorted_by(|a, b| {
+            b.spawners
+                .len()
+                .cmp(&a.spawners.len())
+                .then_with(|| b.positions.cmp(&a.positions))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spots_cover_overlapping_spawners() {
+        let spawners = [
+            (Point { x: 0, y: 0, z: 0 }, 16),
+            (Point { x: 20, y: 0, z: 0 }, 16),
+            (Point { x: 10, y: 0, z: 25 }, 16),
+            (Point { x: 200, y: 0, z: 0 }, 16),
+        ];
+
+        let spots = find_afk_spots(&spawners, 2);
+
+        assert_eq!(spots[0].spawners, vec![0, 1, 2]);
+        assert!(spawners[..3].iter().all(|(spawner, range)| activates(
+            &spots[0].position,
+            spawner,
+            *range
+        )));
+
+        // The lone spawner and the pairs inside the triple aren't reported
+        assert!(spots.iter().all(|spot| spot.spawners.len() == 3));
+
+        // A smaller range leaves only pairs
+        let short = [
+            (Point { x: 0, y: 0, z: 0 }, 12),
+            (Point { x: 20, y: 0, z: 0 }, 12),
+            (Point { x: 10, y: 0, z: 25 }, 12),
+        ];
+        assert!(find_afk_spots(&short, 2)
+            .iter()
+            .all(|spot| spot.spawners.len() == 2));
+
+        // A lone spawner is still a spot of its own when one spawner is enough
+        let alone = find_afk_spots(&spawners[3..], 1);
+        assert_eq!(alone.len(), 1);
+        assert_eq!(alone[0].position.x, 200);
+    }
+
+    #[test]
+    fn oversized_ranges_are_capped() {
+        let spawners = [
+            (Point { x: 0, y: 0, z: 0 }, 16),
+            (Point { x: 20, y: 0, z: 0 }, 16),
+            (Point { x: 10, y: 0, z: 0 }, 1000),
+            (Point { x: 70, y: 0, z: 0 }, i32::MAX as i64),
+        ];
+
+        let spots = find_afk_spots(&spawners, 2);
+
+        assert_eq!(spots[0].spawners, vec![0, 1, 2]);
+        assert!(spots[0].position.x < 20);
+
+        // Only 32 blocks of the huge ranges count, so the far spawner just pairs up
+        // with the middle one
+        assert!(spots.iter().any(|spot| spot.spawners == vec![2, 3]));
+        assert!(spots.iter().all(|spot| spot.spawners.len() <= 3));
+        assert!(spots.iter().all(|spot| {
+            spot.spawners
+                .iter()
+                .all(|i| activates(&spot.position, &spawners[*i].0, MAX_ACTIVATION_RANGE))
+        }));
+    }
+}
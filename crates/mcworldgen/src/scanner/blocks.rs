@@ -1,10 +1,19 @@
-use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+
+use fastnbt::{from_bytes, to_bytes, Value};
+use serde::{de::DeserializeOwned, Deserialize};
+use wildmatch::WildMatch;
 
 use super::points::Point;
 
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct BlockDescriptor {
+    /// The name exactly as it appears in the section palette
     pub name: String,
+
+    /// The normalized name that variants of the same block share
+    pub group: String,
+
     pub x: i64,
     pub y: i64,
     pub z: i64,
@@ -13,6 +22,28 @@ pub struct BlockDescriptor {
 }
 
 impl BlockDescriptor {
+    pub fn new(name: &str, x: i64, y: i64, z: i64) -> Self {
+        Self {
+            name: name.to_string(),
+            group: normalize_name(name),
+            x,
+            y,
+            z,
+            enity: None,
+        }
+    }
+
+    pub fn from_entity(entity: BlockEntity) -> Self {
+        Self {
+            name: entity.id.clone(),
+            group: entity.id.clone(),
+            x: entity.x,
+            y: entity.y,
+            z: entity.z,
+            enity: Some(entity),
+        }
+    }
+
     pub fn pos(&self) -> Point {
         Point {
             x: self.x,
@@ -22,6 +53,53 @@ impl BlockDescriptor {
     }
 }
 
+/// One or more comma separated wildcard patterns, e.g. `*diamond_ore,*emerald_ore`
+#[derive(Clone, Debug)]
+pub struct BlockPatterns(pub Vec<(String, WildMatch)>);
+
+impl BlockPatterns {
+    pub fn parse(patterns: &str) -> Self {
+        Self(
+            patterns
+                .split(',')
+                .map(str::trim)
+                .filter(|pattern| !pattern.is_empty())
+                .map(|pattern| (pattern.to_string(), WildMatch::new(pattern)))
+                .collect(),
+        )
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        self.0.iter().any(|(_, matcher)| matcher.matches(name))
+    }
+}
+
+/// Collapse stone / deepslate / modded stone variants of a block into one name
+pub fn normalize_name(name: &str) -> String {
+    name.trim_end_matches("_stone")
+        .trim_end_matches("_kivi")
+        .trim_end_matches("_deepslate")
+        .replace("deepslate_", "")
+        .replace("aethersteel:aetherslate_", "minecraft:")
+}
+
+/// Count the raw palette names found inside each normalized group
+pub fn count_variants<'a>(
+    blocks: impl Iterator<Item = &'a BlockDescriptor>,
+) -> BTreeMap<String, BTreeMap<String, usize>> {
+    let mut groups = BTreeMap::<String, BTreeMap<String, usize>>::new();
+
+    for block in blocks {
+        *groups
+            .entry(block.group.clone())
+            .or_default()
+            .entry(block.name.clone())
+            .or_default() += 1;
+    }
+
+    groups
+}
+
 impl Ord for BlockDescriptor {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         if self.x < other.x {
@@ -51,11 +129,12 @@ impl PartialOrd for BlockDescriptor {
 /// A Minecraft chunk.
 #[derive(Deserialize, Debug)]
 pub struct ChunkEntityContainer {
-    #[serde(rename = "block_entities")]
-    pub block_entities: Vec<BlockEntity>,
+    /// Kept as raw NBT so one unusual block entity doesn't lose the whole chunk's
+    #[serde(rename = "block_entities", default)]
+    pub block_entities: Vec<Value>,
 }
 
-#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct BlockEntity {
     pub id: String,
     pub x: i64,
@@ -80,18 +159,262 @@ pub struct BlockEntity {
     #[serde(rename = "RequiredPlayerRange")]
     pub activation_range: Option<isize>,
 
+    #[serde(rename = "Delay")]
+    pub delay: Option<isize>,
+
     #[serde(rename = "SpawnData")]
     pub spawn_data: Option<SpawnData>,
+
+    #[serde(rename = "SpawnPotentials")]
+    pub spawn_potentials: Option<Vec<SpawnPotential>>,
+
+    /// The loot table a container fills itself from the first time it's opened
+    #[serde(rename = "LootTable")]
+    pub loot_table: Option<String>,
+
+    #[serde(rename = "LootTableSeed")]
+    pub loot_table_seed: Option<i64>,
+
+    /// Everything the block entity stores, including the fields above
+    #[serde(skip, default = "empty_compound")]
+    pub nbt: Value,
+}
+
+// The raw NBT may hold floats, which can't be `Eq`, so block entities are compared
+// by the fields they're read into
+impl PartialEq for BlockEntity {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.pos() == other.pos()
+            && self.min_spawn_delay == other.min_spawn_delay
+            && self.max_spawn_delay == other.max_spawn_delay
+            && self.spawn_count == other.spawn_count
+            && self.spawn_range == other.spawn_range
+            && self.max_entities == other.max_entities
+            && self.activation_range == other.activation_range
+            && self.delay == other.delay
+            && self.spawn_data == other.spawn_data
+            && self.spawn_potentials == other.spawn_potentials
+            && self.loot_table == other.loot_table
+            && self.loot_table_seed == other.loot_table_seed
+    }
+}
+
+impl Eq for BlockEntity {}
+
+/// The default for the raw `nbt` kept next to the fields read from it
+pub(crate) fn empty_compound() -> Value {
+    Value::Compound(HashMap::new())
+}
+
+/// Read raw NBT into a type
+///
+/// Going through bytes lets smaller integer types widen into the fields, which
+/// `fastnbt::from_value` doesn't.
+pub(crate) fn read_nbt<T: DeserializeOwned>(nbt: &Value) -> Option<T> {
+    from_bytes(&to_bytes(nbt).ok()?).ok()
+}
+
+impl BlockEntity {
+    /// Read the known fields of a block entity while keeping all of its NBT
+    pub fn from_nbt(nbt: Value) -> Option<Self> {
+        let mut entity: Self = read_nbt(&nbt)?;
+        entity.nbt = nbt;
+
+        Some(entity)
+    }
+
+    pub fn pos(&self) -> Point {
+        Point {
+            x: self.x,
+            y: self.y,
+            z: self.z,
+        }
+    }
+
+    /// Every mob a spawner may spawn: the next one followed by its spawn potentials
+    pub fn mobs(&self) -> Vec<&str> {
+        let potentials = self
+            .spawn_potentials
+            .iter()
+            .flatten()
+            .filter_map(|potential| potential.data.as_ref()?.mob());
+
+        let mut mobs = vec![];
+
+        for mob in self
+            .spawn_data
+            .iter()
+            .filter_map(SpawnData::mob)
+            .chain(potentials)
+        {
+            if !mobs.contains(&mob) {
+                mobs.push(mob);
+            }
+        }
+
+        mobs
+    }
 }
 
 #[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct SpawnData {
     #[serde(rename = "entity")]
-    pub entity: SpawnEntity,
+    pub entity: Option<SpawnEntity>,
+
+    pub custom_spawn_rules: Option<SpawnRules>,
+}
+
+impl SpawnData {
+    /// The id of the mob, if the spawner has one
+    pub fn mob(&self) -> Option<&str> {
+        self.entity.as_ref()?.id.as_deref()
+    }
 }
 
 #[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct SpawnEntity {
     #[serde(rename = "id")]
-    pub id: String,
+    pub id: Option<String>,
+}
+
+/// One of the mobs a spawner picks from, with its relative weight
+#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct SpawnPotential {
+    pub weight: Option<i32>,
+    pub data: Option<SpawnData>,
+}
+
+/// Light levels a spawner ignores its usual rules for
+#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct SpawnRules {
+    pub block_light_limit: Option<LightLimit>,
+    pub sky_light_limit: Option<LightLimit>,
+}
+
+/// A light level range, stored as a single value or as inclusive bounds
+#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(untagged)]
+pub enum LightLimit {
+    Level(i32),
+    Range {
+        min_inclusive: i32,
+        max_inclusive: i32,
+    },
+}
+
+impl LightLimit {
+    pub fn range(&self) -> (i32, i32) {
+        match self {
+            LightLimit::Level(level) => (*level, *level),
+            LightLimit::Range {
+                min_inclusive,
+                max_inclusive,
+            } => (*min_inclusive, *max_inclusive),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variants_share_a_group() {
+        assert_eq!(
+            normalize_name("minecraft:deepslate_diamond_ore"),
+            "minecraft:diamond_ore"
+        );
+        assert_eq!(
+            normalize_name("minecraft:diamond_ore"),
+            "minecraft:diamond_ore"
+        );
+
+        let blocks = [
+            BlockDescriptor::new("minecraft:deepslate_diamond_ore", 0, -10, 0),
+            BlockDescriptor::new("minecraft:deepslate_diamond_ore", 1, -10, 0),
+            BlockDescriptor::new("minecraft:diamond_ore", 2, -10, 0),
+        ];
+
+        let groups = count_variants(blocks.iter());
+        let diamonds = &groups["minecraft:diamond_ore"];
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(diamonds["minecraft:deepslate_diamond_ore"], 2);
+        assert_eq!(diamonds["minecraft:diamond_ore"], 1);
+    }
+
+    #[test]
+    fn spawners_with_missing_fields() {
+        fn compound(pairs: Vec<(&str, Value)>) -> Value {
+            Value::Compound(
+                pairs
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), v))
+                    .collect::<HashMap<_, _>>(),
+            )
+        }
+
+        let potential = |mob: &str, weight: i32, rules: Option<Value>| {
+            let mut data = vec![(
+                "entity",
+                compound(vec![("id", Value::String(mob.to_string()))]),
+            )];
+            data.extend(rules.map(|rules| ("custom_spawn_rules", rules)));
+
+            compound(vec![
+                ("weight", Value::Int(weight)),
+                ("data", compound(data)),
+            ])
+        };
+
+        let spawner = compound(vec![
+            ("id", Value::String("minecraft:mob_spawner".to_string())),
+            ("x", Value::Int(1)),
+            ("y", Value::Int(2)),
+            ("z", Value::Int(3)),
+            ("SpawnData", compound(vec![("entity", compound(vec![]))])),
+            (
+                "SpawnPotentials",
+                Value::List(vec![
+                    potential(
+                        "minecraft:blaze",
+                        3,
+                        Some(compound(vec![
+                            (
+                                "block_light_limit",
+                                compound(vec![
+                                    ("min_inclusive", Value::Int(0)),
+                                    ("max_inclusive", Value::Int(11)),
+                                ]),
+                            ),
+                            ("sky_light_limit", Value::Int(0)),
+                        ])),
+                    ),
+                    potential("minecraft:magma_cube", 1, None),
+                ]),
+            ),
+        ]);
+
+        let entity = BlockEntity::from_nbt(spawner.clone()).unwrap();
+        assert_eq!(entity.nbt, spawner);
+
+        assert_eq!(entity.min_spawn_delay, None);
+        assert_eq!(
+            entity.mobs(),
+            vec!["minecraft:blaze", "minecraft:magma_cube"]
+        );
+
+        let potentials = entity.spawn_potentials.unwrap();
+        let rules = potentials[0]
+            .data
+            .as_ref()
+            .unwrap()
+            .custom_spawn_rules
+            .as_ref()
+            .unwrap();
+
+        assert_eq!(rules.block_light_limit.as_ref().unwrap().range(), (0, 11));
+        assert_eq!(rules.sky_light_limit.as_ref().unwrap().range(), (0, 0));
+    }
 }
@@ -32,6 +32,13 @@ impl Bounds {
         }
     }
 
+    /// The number of blocks inside the box
+    pub fn volume(&self) -> i64 {
+        (self.max.x - self.min.x + 1)
+            * (self.max.y - self.min.y + 1)
+            * (self.max.z - self.min.z + 1)
+    }
+
     pub fn from_point(point: &Point) -> Bounds {
         Self {
             min: point.clone(),
@@ -54,6 +61,155 @@ impl Bounds {
         }
     }
 
+    /// The same footprint spanning every possible Y level
+    pub fn columns(&self) -> Bounds {
+        Bounds {
+            min: Point {
+                y: i64::MIN,
+                ..self.min.clone()
+            },
+            max: Point {
+                y: i64::MAX,
+                ..self.max.clone()
+            },
+        }
+    }
+
+    /// The blocks covered by the region file at the given region coordinates
+    pub fn of_region(rx: i64, rz: i64) -> Bounds {
+        Self::of_columns(rx * 512, rz * 512, 512)
+    }
+
+    /// The blocks covered by the chunk at the given (absolute) chunk coordinates
+    pub fn of_chunk(cx: i64, cz: i64) -> Bounds {
+        Self::of_columns(cx * 16, cz * 16, 16)
+    }
+
+    /// The blocks covered by the section at the given (absolute) section coordinates
+    pub fn of_section(cx: i64, sy: i64, cz: i64) -> Bounds {
+        Bounds {
+            min: Point {
+                x: cx * 16,
+                y: sy * 16,
+                z: cz * 16,
+            },
+            max: Point {
+                x: cx * 16 + 15,
+                y: sy * 16 + 15,
+                z: cz * 16 + 15,
+            },
+        }
+    }
+
+    // A square footprint that spans every possible Y level
+    fn of_columns(x: i64, z: i64, size: i64) -> Bounds {
+        Bounds {
+            min: Point { x, y: i64::MIN, z },
+            max: Point {
+                x: x + size - 1,
+                y: i64::MAX,
+                z: z + size - 1,
+            },
+        }
+    }
+
+    pub fn intersects(&self, other: &Bounds) -> bool {
+        self.min.x <= other.max.x
+            && self.max.x >= other.min.x
+            && self.min.y <= other.max.y
+            && self.max.y >= other.min.y
+            && self.min.z <= other.max.z
+            && self.max.z >= other.min.z
+    }
+
+    /// The smallest box that contains both boxes
+    pub fn union(&self, other: &Bounds) -> Bounds {
+        Bounds {
+            min: Point {
+                x: self.min.x.min(other.min.x),
+                y: self.min.y.min(other.min.y),
+                z: self.min.z.min(other.min.z),
+            },
+            max: Point {
+                x: self.max.x.max(other.max.x),
+                y: self.max.y.max(other.max.y),
+                z: self.max.z.max(other.max.z),
+            },
+        }
+    }
+
+    /// The box both boxes share, if they overlap
+    pub fn intersection(&self, other: &Bounds) -> Option<Bounds> {
+        self.intersects(other).then(|| Bounds {
+            min: Point {
+                x: self.min.x.max(other.min.x),
+                y: self.min.y.max(other.min.y),
+                z: self.min.z.max(other.min.z),
+            },
+            max: Point {
+                x: self.max.x.min(other.max.x),
+                y: self.max.y.min(other.max.y),
+                z: self.max.z.min(other.max.z),
+            },
+        })
+    }
+
+    /// The point inside the box that is closest to the given point
+    pub fn closest_to(&self, point: &Point) -> Point {
+        Point {
+            x: point.x.clamp(self.min.x, self.max.x),
+            y: point.y.clamp(self.min.y, self.max.y),
+            z: point.z.clamp(self.min.z, self.max.z),
+        }
+    }
+
+    pub fn corners(&self) -> [Point; 8] {
+        let (min, max) = (&self.min, &self.max);
+
+        [
+            Point {
+                x: min.x,
+                y: min.y,
+                z: min.z,
+            },
+            Point {
+                x: max.x,
+                y: min.y,
+                z: min.z,
+            },
+            Point {
+                x: min.x,
+                y: max.y,
+                z: min.z,
+            },
+            Point {
+                x: max.x,
+                y: max.y,
+                z: min.z,
+            },
+            Point {
+                x: min.x,
+                y: min.y,
+                z: max.z,
+            },
+            Point {
+                x: max.x,
+                y: min.y,
+                z: max.z,
+            },
+            Point {
+                x: min.x,
+                y: max.y,
+                z: max.z,
+            },
+            Point {
+                x: max.x,
+                y: max.y,
+                z: max.z,
+            },
+        ]
+    }
+
     pub fn contains(&self, point: &Point) -> bool {
         point.x >= self.min.x
             && point.x <= self.max.x
@@ -63,3 +219,24 @@ impl Bounds {
             && point.z <= self.max.z
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn footprints_intersect_search_area() {
+        let area = Bounds::from_point(&Point { x: 0, y: 8, z: 0 }).expand(&Point::from(100));
+
+        assert!(area.intersects(&Bounds::of_region(0, 0)));
+        assert!(area.intersects(&Bounds::of_region(-1, -1)));
+        assert!(!area.intersects(&Bounds::of_region(1, 0)));
+
+        assert!(area.intersects(&Bounds::of_chunk(6, -7)));
+        assert!(!area.intersects(&Bounds::of_chunk(7, 0)));
+
+        assert!(area.intersects(&Bounds::of_section(0, 6, 0)));
+        assert!(!area.intersects(&Bounds::of_section(0, 7, 0)));
+        assert!(!area.intersects(&Bounds::of_section(0, -7, 0)));
+    }
+}
@@ -0,0 +1,256 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt,
+    str::FromStr,
+};
+
+use super::{
+    census::AIR_BLOCKS,
+    lookup::BlockLookup,
+    points::{Connectivity, Point},
+    shapes::Shape,
+    voxels::VoxelStore,
+};
+
+/// The direction tunnels are dug in
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Axis {
+    X,
+    Z,
+}
+
+impl FromStr for Axis {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "x" => Ok(Axis::X),
+            "z" => Ok(Axis::Z),
+            _ => Err(format!("Unknown tunnel direction '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for Axis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Axis::X => write!(f, "x"),
+            Axis::Z => write!(f, "z"),
+        }
+    }
+}
+
+/// The cross-section of a tunnel, e.g. `1x2` for a tunnel the player can walk through
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TunnelShape {
+    pub width: i64,
+    pub height: i64,
+}
+
+impl FromStr for TunnelShape {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid tunnel shape '{}', expected WIDTHxHEIGHT", s);
+
+        let (width, height) = s.split_once('x').ok_or_else(invalid)?;
+        let width = width.parse::<i64>().map_err(|_| invalid())?;
+        let height = height.parse::<i64>().map_err(|_| invalid())?;
+
+        match width > 0 && height > 0 {
+            true => Ok(Self { width, height }),
+            false => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for TunnelShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// One way of branch mining
+#[derive(Clone, Copy, Debug)]
+pub struct Strategy {
+    /// The Y level of the tunnel floors
+    pub y: i64,
+
+    /// How many blocks are left between neighboring tunnels
+    pub spacing: i64,
+
+    pub shape: TunnelShape,
+    pub axis: Axis,
+}
+
+/// What a strategy digs out and finds
+pub struct Yield {
+    /// Solid blocks that had to be mined
+    pub mined: usize,
+
+    /// Matches that are dug out or can be seen from the tunnels
+    pub revealed: usize,
+
+    /// Veins with at least one revealed block
+    pub veins: usize,
+
+    /// All blocks of the revealed veins, as following a vein collects all of it
+    pub vein_blocks: usize,
+}
+
+/// Dig parallel tunnels through the area, one through the origin and the others
+/// every `spacing` blocks to either side of it
+pub fn simulate(
+    strategy: &Strategy,
+    origin: &Point,
+    area: &dyn Shape,
+    ore: &VoxelStore<usize>,
+    vein_sizes: &[usize],
+    lookup: &mut BlockLookup,
+) -> Yield {
+    let bounds = area.bounds();
+    let Strategy {
+        y,
+        spacing,
+        shape,
+        axis,
+    } = *strategy;
+
+    // Positions along and across the tunnels
+    let (along, across, start) = match axis {
+        Axis::X => (
+            bounds.min.x..=bounds.max.x,
+            (bounds.min.z, bounds.max.z),
+            origin.z,
+        ),
+        Axis::Z => (
+            bounds.min.z..=bounds.max.z,
+            (bounds.min.x, bounds.max.x),
+            origin.x,
+        ),
+    };
+
+    let step = shape.width + spacing.max(0);
+    let first = start - (start - across.0).div_euclid(step) * step;
+
+    let mut tunnels = HashSet::new();
+
+    for offset in (first..=across.1).step_by(step as usize) {
+        for a in along.clone() {
+            for w in 0..shape.width {
+                for h in 0..shape.height {
+                    let point = match axis {
+                        Axis::X => Point {
+                            x: a,
+                            y: y + h,
+                            z: offset + w,
+                        },
+                        Axis::Z => Point {
+                            x: offset + w,
+                            y: y + h,
+                            z: a,
+                        },
+                    };
+
+                    if area.contains(&point) {
+                        tunnels.insert(point);
+                    }
+                }
+            }
+        }
+    }
+
+    let mined = tunnels
+        .iter()
+        .filter(|point| {
+            lookup
+                .block_at(point)
+                .is_some_and(|name| !AIR_BLOCKS.contains(&name))
+        })
+        .count();
+
+    // Ore is revealed when it's dug out or one of its faces is uncovered
+    let revealed = tunnels
+        .iter()
+        .flat_map(|point| {
+            point
+                .neighbors(Connectivity::Faces, 1)
+                .chain([point.clone()])
+        })
+        .filter(|point| ore.get(point).is_some())
+        .collect::<HashSet<_>>();
+
+    let veins = revealed
+        .iter()
+        .filter_map(|point| ore.get(point).copied())
+        .collect::<BTreeSet<_>>();
+
+    Yield {
+        mined,
+        revealed: revealed.len(),
+        veins: veins.len(),
+        vein_blocks: veins.iter().map(|index| vein_sizes[*index]).sum(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::scanner::bounds::Bounds;
+
+    #[test]
+    fn tunnels_through_a_section() {
+        let mut lookup = BlockLookup::new(Path::new("missing"));
+
+        // Stone with a pocket of air at the start of the tunnel through the origin
+        lookup.set_section((0, 0, 0), |point| match (point.x, point.y, point.z) {
+            (0..=3, 4..=5, 5) => ("minecraft:cave_air", 0),
+            _ => ("minecraft:stone", 0),
+        });
+
+        let area = Bounds::of_section(0, 0, 0);
+
+        let mut ore = VoxelStore::new();
+        for (index, (x, y, z)) in [
+            (0, (7, 6, 5)),
+            (0, (7, 7, 5)),
+            (1, (10, 4, 3)),
+            (2, (10, 8, 0)),
+            (3, (12, 4, 8)),
+        ] {
+            ore.insert(Point { x, y, z }, index);
+        }
+        let vein_sizes = [2, 1, 1, 1];
+
+        let strategy = Strategy {
+            y: 4,
+            spacing: 2,
+            shape: TunnelShape {
+                width: 1,
+                height: 2,
+            },
+            axis: Axis::X,
+        };
+
+        // Tunnels at Z 2, 5, 8, 11 and 14, each 16 blocks long and 2 high
+        let origin = Point { x: 0, y: 4, z: 5 };
+        let result = simulate(&strategy, &origin, &area, &ore, &vein_sizes, &mut lookup);
+
+        assert_eq!(result.mined, 5 * 16 * 2 - 8);
+
+        // The ore above a tunnel and beside one is seen, the ore in one is dug out
+        assert_eq!(result.revealed, 3);
+        assert_eq!(result.veins, 3);
+        assert_eq!(result.vein_blocks, 4);
+
+        // Moving the origin shifts the tunnels to Z 0, 3, 6, 9, 12 and 15
+        let origin = Point { x: 0, y: 4, z: 6 };
+        let result = simulate(&strategy, &origin, &area, &ore, &vein_sizes, &mut lookup);
+
+        assert_eq!(result.mined, 6 * 16 * 2);
+        assert_eq!(result.revealed, 2);
+        assert_eq!(result.vein_blocks, 2);
+    }
+}
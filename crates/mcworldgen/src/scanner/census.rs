@@ -0,0 +1,310 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+};
+
+use fastanvil::{JavaChunk, Region};
+use itertools::Itertools;
+
+use super::{bounds::Bounds, points::Point, shapes::Shape};
+
+/// Blocks that don't count towards the non-air total
+pub const AIR_BLOCKS: [&str; 3] = ["minecraft:air", "minecraft:cave_air", "minecraft:void_air"];
+
+/// An exact count of every block type in part of a dimension
+pub struct Census {
+    pub chunks: usize,
+    pub sections: usize,
+    pub counts: HashMap<String, u64>,
+}
+
+impl Census {
+    pub fn new() -> Self {
+        Self {
+            chunks: 0,
+            sections: 0,
+            counts: HashMap::new(),
+        }
+    }
+
+    pub fn combine(list: impl Iterator<Item = Self>) -> Self {
+        let mut census = Census::new();
+
+        for result in list {
+            census.chunks += result.chunks;
+            census.sections += result.sections;
+
+            for (name, count) in result.counts {
+                *census.counts.entry(name).or_default() += count;
+            }
+        }
+
+        census
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    pub fn air(&self) -> u64 {
+        AIR_BLOCKS
+            .iter()
+            .map(|name| self.counts.get(*name).copied().unwrap_or(0))
+            .sum()
+    }
+
+    pub fn non_air(&self) -> u64 {
+        self.total() - self.air()
+    }
+
+    /// Non-air block counts grouped by namespace, largest first
+    pub fn by_namespace(&self) -> Vec<(String, Vec<(&str, u64)>)> {
+        let mut namespaces = BTreeMap::<String, Vec<(&str, u64)>>::new();
+
+        for (name, count) in &self.counts {
+            if AIR_BLOCKS.contains(&name.as_str()) {
+                continue;
+            }
+
+            let namespace = name.split_once(':').map_or("minecraft", |(ns, _)| ns);
+
+            namespaces
+                .entry(namespace.to_string())
+                .or_default()
+                .push((name.as_str(), *count));
+        }
+
+        namespaces
+            .into_iter()
+            .map(|(namespace, mut blocks)| {
+                blocks.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+                (namespace, blocks)
+            })
+            .sorted_by_key(|(_, blocks)| std::cmp::Reverse(blocks.iter().map(|b| b.1).sum::<u64>()))
+            .collect()
+    }
+}
+
+pub fn census_region(area: &dyn Shape, region: &mut Region<File>, rx: i64, rz: i64) -> Census {
+    let mut census = Census::new();
+
+    for (x, z) in (0..32i64).flat_map(|z| (0..32i64).map(move |x| (x, z))) {
+        let (cx, cz) = (rx * 32 + x, rz * 32 + z);
+
+        if !area.intersects(&Bounds::of_chunk(cx, cz)) {
+            continue;
+        }
+
+        let chunk = match region.read_chunk(x as usize, z as usize) {
+            Ok(Some(data)) => JavaChunk::from_bytes(&data).ok(),
+            _ => None,
+        };
+
+        let tower = match chunk {
+            Some(JavaChunk::Post18(chunk)) => chunk.sections,
+            _ => None,
+        };
+
+        let Some(tower) = tower else {
+            continue;
+        };
+
+        census.chunks += 1;
+
+        for section in tower.sections() {
+            let sy = section.y as i64;
+            let bounds = Bounds::of_section(cx, sy, cz);
+
+            if !area.intersects(&bounds) {
+                continue;
+            }
+
+            census.sections += 1;
+
+            let palette = section.block_states.palette();
+            let indices = section.block_states.try_iter_indices();
+
+            let Some(counts) = count_section(area, &bounds, palette.len(), indices) else {
+                continue;
+            };
+
+            for (block, count) in palette.iter().zip(counts) {
+                if count > 0 {
+                    *census.counts.entry(block.name().to_string()).or_default() += count;
+                }
+            }
+        }
+    }
+
+    census
+}
+
+/// How many of each palette entry of a section lie inside the area
+///
+/// Sections made of a single block type don't store any indices, and are only
+/// counted block by block when the area cuts through them. Returns `None` for a
+/// section that has no indices but more than one palette entry.
+fn count_section(
+    area: &dyn Shape,
+    bounds: &Bounds,
+    palette_len: usize,
+    indices: Option<impl Iterator<Item = usize>>,
+) -> Option<Vec<u64>> {
+    let mut counts = vec![0u64; palette_len];
+    let covered = area.covers(bounds);
+
+    match indices {
+        None if palette_len == 1 => match covered {
+            true => counts[0] = 4096,
+            false => {
+                counts[0] = (0..4096)
+                    .filter(|i| area.contains(&section_pos(bounds, *i)))
+                    .count() as u64
+            }
+        },
+        None => return None,
+        Some(iter) => {
+            for (i, index) in iter.enumerate() {
+                if covered || area.contains(&section_pos(bounds, i)) {
+                    counts[index] += 1;
+                }
+            }
+        }
+    }
+
+    Some(counts)
+}
+
+/// The position of the i-th block of a section, in the order block indices are stored
+pub fn section_pos(section: &Bounds, i: usize) -> Point {
+    Point {
+        x: section.min.x + (i & 0x000F) as i64,
+        y: section.min.y + ((i & 0x0F00) >> 8) as i64,
+        z: section.min.z + ((i & 0x00F0) >> 4) as i64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// A box that counts how often it's asked about single blocks
+    struct Counted(Bounds, AtomicUsize);
+
+    impl Shape for Counted {
+        fn contains(&self, point: &Point) -> bool {
+            self.1.fetch_add(1, Ordering::Relaxed);
+            self.0.contains(point)
+        }
+
+        fn bounds(&self) -> Bounds {
+            self.0.clone()
+        }
+
+        fn covers(&self, area: &Bounds) -> bool {
+            self.0.covers(area)
+        }
+    }
+
+    fn area(min: (i64, i64, i64), max: (i64, i64, i64)) -> Counted {
+        let bounds = Bounds {
+            min: Point {
+                x: min.0,
+                y: min.1,
+                z: min.2,
+            },
+            max: Point {
+                x: max.0,
+                y: max.1,
+                z: max.2,
+            },
+        };
+
+        Counted(bounds, AtomicUsize::new(0))
+    }
+
+    #[test]
+    fn uniform_sections_are_counted_at_once_when_covered() {
+        let section = Bounds::of_section(0, -4, 0);
+        let none = None::<std::vec::IntoIter<usize>>;
+
+        let whole = area((-100, -100, -100), (100, 100, 100));
+        assert_eq!(
+            count_section(&whole, &section, 1, none.clone()),
+            Some(vec![4096])
+        );
+        assert_eq!(whole.1.load(Ordering::Relaxed), 0);
+
+        // Half of the section's layers
+        let half = area((-100, -64, -100), (100, -57, 100));
+        assert_eq!(
+            count_section(&half, &section, 1, none.clone()),
+            Some(vec![2048])
+        );
+        assert_eq!(half.1.load(Ordering::Relaxed), 4096);
+
+        // Without indices only a single palette entry can be told apart
+        assert_eq!(count_section(&whole, &section, 2, none), None);
+    }
+
+    #[test]
+    fn mixed_sections_count_each_palette_entry() {
+        let section = Bounds::of_section(1, 0, 0);
+
+        // The bottom layer is entry 1, everything above it entry 0
+        let indices = || (0..4096).map(|i| usize::from(i < 256));
+
+        let whole = area((0, 0, 0), (100, 100, 100));
+        assert_eq!(
+            count_section(&whole, &section, 2, Some(indices())),
+            Some(vec![3840, 256])
+        );
+        assert_eq!(whole.1.load(Ordering::Relaxed), 0);
+
+        // The bottom two layers of one column of the section
+        let column = area((16, 0, 0), (16, 1, 0));
+        assert_eq!(
+            count_section(&column, &section, 2, Some(indices())),
+            Some(vec![1, 1])
+        );
+    }
+
+    #[test]
+    fn namespaces_without_air() {
+        let mut census = Census::new();
+        for (name, count) in [
+            ("minecraft:air", 5000),
+            ("minecraft:cave_air", 100),
+            ("minecraft:stone", 900),
+            ("minecraft:dirt", 100),
+            ("create:zinc_ore", 600),
+            ("create:limestone", 600),
+            ("plain", 1),
+        ] {
+            census.counts.insert(name.to_string(), count);
+        }
+
+        assert_eq!(census.total(), 7301);
+        assert_eq!(census.air(), 5100);
+        assert_eq!(census.non_air(), 2201);
+        assert_eq!(
+            census.by_namespace(),
+            vec![
+                (
+                    "create".to_string(),
+                    vec![("create:limestone", 600), ("create:zinc_ore", 600)]
+                ),
+                (
+                    "minecraft".to_string(),
+                    vec![
+                        ("minecraft:stone", 900),
+                        ("minecraft:dirt", 100),
+                        ("plain", 1)
+                    ]
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,236 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, Read},
+    path::Path,
+};
+
+use fastnbt::{from_bytes, Value};
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+
+use super::{
+    blocks::{read_nbt, ChunkEntityContainer},
+    entities::{position_of, scan_entities},
+    items::Item,
+    points::Point,
+    scan::{read_chunks, scan_regions},
+    shapes::Shape,
+};
+
+/// Something holding items: a chest or furnace, an entity or a player
+#[derive(Debug, Clone, PartialEq)]
+pub struct Container {
+    /// The block or entity id, or the player's UUID
+    pub id: String,
+    pub kind: ContainerKind,
+    pub position: Point,
+    pub items: Vec<Item>,
+
+    /// Everything the block entity, entity or player stores
+    pub nbt: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerKind {
+    Block,
+    Entity,
+    Player,
+    EnderChest,
+}
+
+#[derive(Deserialize, Debug)]
+struct ItemBlockEntity {
+    id: String,
+    x: i64,
+    y: i64,
+    z: i64,
+
+    #[serde(rename = "Items")]
+    items: Option<Vec<Item>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ItemEntity {
+    id: String,
+
+    #[serde(rename = "Pos")]
+    pos: Vec<f64>,
+
+    /// Minecart chests and hoppers
+    #[serde(rename = "Items")]
+    items: Option<Vec<Item>>,
+
+    /// Item frames and dropped items
+    #[serde(rename = "Item")]
+    item: Option<Item>,
+
+    /// Armor stands and mobs before 1.21.5
+    #[serde(rename = "ArmorItems")]
+    armor: Option<Vec<Item>>,
+
+    #[serde(rename = "HandItems")]
+    hands: Option<Vec<Item>>,
+
+    /// Armor stands and mobs since 1.21.5, keyed by slot
+    equipment: Option<HashMap<String, Item>>,
+
+    /// Villagers, allays and the like
+    #[serde(rename = "Inventory")]
+    inventory: Option<Vec<Item>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PlayerData {
+    #[serde(rename = "Pos")]
+    pos: Vec<f64>,
+
+    /// A dimension id, or -1, 0 and 1 before 1.16
+    #[serde(rename = "Dimension")]
+    dimension: Option<Value>,
+
+    #[serde(rename = "Inventory", default)]
+    inventory: Vec<Item>,
+
+    #[serde(rename = "EnderItems", default)]
+    ender_items: Vec<Item>,
+}
+
+/// Every container in the area of a dimension that holds at least one item
+pub fn find_containers(path: &Path, area: &dyn Shape) -> io::Result<Vec<Container>> {
+    let (blocks, _) = scan_regions(path, area, |region, rx, rz| {
+        read_chunks(region, rx, rz, area, |chunk: ChunkEntityContainer| {
+            chunk
+                .block_entities
+                .into_iter()
+                .filter_map(|nbt| Some((read_nbt::<ItemBlockEntity>(&nbt)?, nbt)))
+                .map(|(entity, nbt)| Container {
+                    id: entity.id,
+                    kind: ContainerKind::Block,
+                    position: Point {
+                        x: entity.x,
+                        y: entity.y,
+                        z: entity.z,
+                    },
+                    items: entity.items.unwrap_or_default(),
+                    nbt,
+                })
+                .collect()
+        })
+    })?;
+
+    let entities = scan_entities(path, area, entity_container)?;
+
+    let mut containers = blocks
+        .into_iter()
+        .flatten()
+        .chain(entities)
+        .chain(find_players(path)?)
+        .filter(|container| !container.items.is_empty() && area.contains(&container.position))
+        .collect::<Vec<_>>();
+
+    containers.sort_by_key(|container| {
+        let Point { x, y, z } = container.position;
+        (x, y, z)
+    });
+
+    Ok(containers)
+}
+
+fn entity_container(nbt: Value) -> Option<Container> {
+    let entity = read_nbt::<ItemEntity>(&nbt)?;
+    let position = position_of(&entity.pos)?;
+
+    let items = entity
+        .items
+        .into_iter()
+        .flatten()
+        .chain(entity.item)
+        .chain(entity.armor.into_iter().flatten())
+        .chain(entity.hands.into_iter().flatten())
+        .chain(entity.equipment.into_iter().flat_map(HashMap::into_values))
+        .chain(entity.inventory.into_iter().flatten())
+        .filter(|item| !item.is_empty())
+        .collect();
+
+    Some(Container {
+        id: entity.id,
+        kind: ContainerKind::Entity,
+        position,
+        items,
+        nbt,
+    })
+}
+
+/// The inventories and ender chests of the players last seen in a dimension
+///
+/// Player data lives in the world directory, which is the dimension's own
+/// directory for the overworld and its parent for the others.
+fn find_players(path: &Path) -> io::Result<Vec<Container>> {
+    let (world, dimension) = match path.file_name().and_then(|name| name.to_str()) {
+        Some("DIM-1") => (path.parent(), "minecraft:the_nether"),
+        Some("DIM1") => (path.parent(), "minecraft:the_end"),
+        _ => (Some(path), "minecraft:overworld"),
+    };
+
+    let Some(Ok(files)) = world.map(|world| fs::read_dir(world.join("playerdata"))) else {
+        return Ok(vec![]);
+    };
+
+    let mut containers = vec![];
+
+    for file in files {
+        let file = file?.path();
+        if file.extension().and_then(|ext| ext.to_str()) != Some("dat") {
+            continue;
+        }
+
+        let mut data = vec![];
+        GzDecoder::new(File::open(&file)?).read_to_end(&mut data)?;
+
+        let (Ok(player), Ok(nbt)) = (from_bytes::<PlayerData>(&data), from_bytes::<Value>(&data))
+        else {
+            continue;
+        };
+
+        let seen_in = match &player.dimension {
+            Some(Value::String(id)) => id.as_str(),
+            Some(value) => match value.as_i64() {
+                Some(-1) => "minecraft:the_nether",
+                Some(1) => "minecraft:the_end",
+                _ => "minecraft:overworld",
+            },
+            None => "minecraft:overworld",
+        };
+
+        if seen_in != dimension {
+            continue;
+        }
+
+        let Some(position) = position_of(&player.pos) else {
+            continue;
+        };
+
+        let uuid = file
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        containers.push(Container {
+            id: uuid.clone(),
+            kind: ContainerKind::Player,
+            position: position.clone(),
+            items: player.inventory,
+            nbt: nbt.clone(),
+        });
+        containers.push(Container {
+            id: uuid,
+            kind: ContainerKind::EnderChest,
+            position,
+            items: player.ender_items,
+            nbt,
+        });
+    }
+
+    Ok(containers)
+}
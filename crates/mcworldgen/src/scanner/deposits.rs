@@ -0,0 +1,150 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use itertools::Itertools;
+
+use super::{bounds::Bounds, points::Point, veins::Vein};
+
+/// What DBSCAN clusters into deposits
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ClusterBy {
+    /// The center of each vein, so every vein counts once regardless of its size
+    Veins,
+
+    /// Every matched block. Slow for large searches
+    Blocks,
+}
+
+impl FromStr for ClusterBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "veins" => Ok(ClusterBy::Veins),
+            "blocks" => Ok(ClusterBy::Blocks),
+            _ => Err(format!("Unknown deposit clustering '{}'", s)),
+        }
+    }
+}
+
+/// A dense area of ore made of one or more veins
+pub struct Deposit {
+    /// The mean position of the deposit's blocks
+    pub center: Point,
+    pub bounds: Bounds,
+    pub blocks: usize,
+    pub veins: usize,
+}
+
+/// Cluster veins into deposits with DBSCAN
+///
+/// Points within `eps` blocks of each other are neighbors, and a point with at
+/// least `min_points` neighbors (itself included) starts a cluster. Points that
+/// don't belong to any cluster are left out.
+pub fn find_deposits(veins: &[Vein], by: ClusterBy, eps: f64, min_points: usize) -> Vec<Deposit> {
+    // Each clustered point along with the index of the vein it belongs to
+    let (points, owners): (Vec<Point>, Vec<usize>) = match by {
+        ClusterBy::Veins => veins
+            .iter()
+            .enumerate()
+            .map(|(index, vein)| (vein.center.clone(), index))
+            .unzip(),
+        ClusterBy::Blocks => veins
+            .iter()
+            .enumerate()
+            .flat_map(|(index, vein)| vein.blocks.iter().map(move |block| (block.pos(), index)))
+            .unzip(),
+    };
+
+    let input = points
+        .iter()
+        .map(|point| vec![point.x as f64, point.y as f64, point.z as f64])
+        .collect_vec();
+
+    let mut clusters = BTreeMap::<usize, Vec<usize>>::new();
+
+    for (index, class) in dbscan::cluster(eps, min_points, &input)
+        .into_iter()
+        .enumerate()
+    {
+        match class {
+            dbscan::Classification::Core(cluster) | dbscan::Classification::Edge(cluster) => {
+                clusters.entry(cluster).or_default().push(index)
+            }
+            dbscan::Classification::Noise => {}
+        }
+    }
+
+    clusters
+        .into_values()
+        .map(|members| {
+            let blocks = match by {
+                ClusterBy::Veins => members
+                    .iter()
+                    .flat_map(|index| veins[owners[*index]].blocks.iter().map(|block| block.pos()))
+                    .collect_vec(),
+                ClusterBy::Blocks => members
+                    .iter()
+                    .map(|index| points[*index].clone())
+                    .collect_vec(),
+            };
+
+            Deposit::from_blocks(
+                &blocks,
+                members.iter().map(|index| owners[*index]).unique().count(),
+            )
+        })
+        .sorted_by(|a, b| b.blocks.cmp(&a.blocks))
+        .collect()
+}
+
+impl Deposit {
+    fn from_blocks(blocks: &[Point], veins: usize) -> Self {
+        let bounds = blocks
+            .iter()
+            .map(Bounds::from_point)
+            .reduce(|a, b| a.union(&b))
+            .unwrap_or(Bounds::ZERO);
+
+        let count = blocks.len().max(1) as i64;
+        let center = Point {
+            x: blocks.iter().map(|p| p.x).sum::<i64>().div_euclid(count),
+            y: blocks.iter().map(|p| p.y).sum::<i64>().div_euclid(count),
+            z: blocks.iter().map(|p| p.z).sum::<i64>().div_euclid(count),
+        };
+
+        Self {
+            center,
+            bounds,
+            blocks: blocks.len(),
+            veins,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{blocks::BlockDescriptor, veins::Vein};
+
+    fn vein(x: i64, size: usize) -> Vein {
+        Vein::from_iter(
+            (0..size as i64).map(|y| BlockDescriptor::new("minecraft:diamond_ore", x, y, 0)),
+        )
+        .with_computed_bounds()
+    }
+
+    #[test]
+    fn clusters_veins() {
+        let veins = [vein(0, 2), vein(4, 3), vein(8, 1), vein(100, 5)];
+
+        let deposits = find_deposits(&veins, ClusterBy::Veins, 5.0, 2);
+        assert_eq!(deposits.len(), 1);
+        assert_eq!(deposits[0].veins, 3);
+        assert_eq!(deposits[0].blocks, 6);
+        assert_eq!(deposits[0].bounds.max.x, 8);
+
+        // Only the blocks of the two larger veins are dense enough
+        let deposits = find_deposits(&veins, ClusterBy::Blocks, 2.0, 3);
+        assert_eq!(deposits.iter().map(|d| d.blocks).collect_vec(), vec![5, 3]);
+    }
+}
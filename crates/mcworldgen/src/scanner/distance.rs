@@ -0,0 +1,213 @@
+use std::{path::Path, str::FromStr};
+
+use super::points::Point;
+
+/// How the distance between two points is measured
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Metric {
+    /// Straight line distance
+    Euclidean,
+
+    /// Straight line distance ignoring Y
+    Horizontal,
+
+    /// The number of blocks you'd have to dig through
+    Manhattan,
+
+    /// The largest distance along any one axis
+    Chebyshev,
+}
+
+impl Metric {
+    pub fn distance(&self, a: &Point, b: &Point) -> f64 {
+        let dx = (a.x - b.x).abs() as f64;
+        let dy = (a.y - b.y).abs() as f64;
+        let dz = (a.z - b.z).abs() as f64;
+
+        match self {
+            Metric::Euclidean => (dx * dx + dy * dy + dz * dz).sqrt(),
+            Metric::Horizontal => (dx * dx + dz * dz).sqrt(),
+            Metric::Manhattan => dx + dy + dz,
+            Metric::Chebyshev => dx.max(dy).max(dz),
+        }
+    }
+}
+
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "euclidean" => Ok(Metric::Euclidean),
+            "horizontal" => Ok(Metric::Horizontal),
+            "manhattan" => Ok(Metric::Manhattan),
+            "chebyshev" => Ok(Metric::Chebyshev),
+            _ => Err(format!("Unknown distance metric '{}'", s)),
+        }
+    }
+}
+
+/// The horizontal coordinate scale of a dimension relative to the Overworld
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Scale {
+    Overworld,
+    Nether,
+}
+
+impl Scale {
+    /// Guess the scale of a dimension from its folder name
+    pub fn of_dimension(path: &Path) -> Scale {
+        match path.file_name().and_then(|name| name.to_str()) {
+            Some("DIM-1") | Some("the_nether") => Scale::Nether,
+            _ => Scale::Overworld,
+        }
+    }
+
+    fn factor(&self) -> i64 {
+        match self {
+            Scale::Overworld => 1,
+            Scale::Nether => 8,
+        }
+    }
+}
+
+impl FromStr for Scale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "overworld" => Ok(Scale::Overworld),
+            "nether" => Ok(Scale::Nether),
+            _ => Err(format!("Unknown dimension scale '{}'", s)),
+        }
+    }
+}
+
+impl Point {
+    /// Convert a position into the coordinates of a dimension with a different scale
+    pub fn rescale(&self, from: Scale, to: Scale) -> Point {
+        Point {
+            x: (self.x * from.factor()).div_euclid(to.factor()),
+            y: self.y,
+            z: (self.z * from.factor()).div_euclid(to.factor()),
+        }
+    }
+}
+
+/// The point distances are measured from
+pub struct Origin {
+    /// The origin in the coordinates of its own dimension
+    pub point: Point,
+    pub scale: Scale,
+
+    /// The scale of the dimension being scanned
+    pub dimension: Scale,
+
+    pub metric: Metric,
+}
+
+impl Origin {
+    /// The origin in the coordinates of the dimension being scanned
+    pub fn local(&self) -> Point {
+        self.point.rescale(self.scale, self.dimension)
+    }
+
+    /// A distance along each axis in the origin's dimension, e.g. the threshold, in
+    /// blocks of the dimension being scanned
+    ///
+    /// Rounds up so the area around the origin is never cut short.
+    pub fn local_extent(&self, extent: &Point) -> Point {
+        let (from, to) = (self.scale.factor(), self.dimension.factor());
+
+        Point {
+            x: (extent.x * from + to - 1).div_euclid(to),
+            y: extent.y,
+            z: (extent.z * from + to - 1).div_euclid(to),
+        }
+    }
+
+    /// The distance to a scanned position, measured in the origin's dimension
+    pub fn distance_to(&self, point: &Point) -> f64 {
+        self.metric
+            .distance(&self.point, &point.rescale(self.dimension, self.scale))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metrics() {
+        let a = Point { x: 0, y: 0, z: 0 };
+        let b = Point { x: 3, y: -12, z: 4 };
+
+        assert_eq!(Metric::Euclidean.distance(&a, &b), 13.0);
+        assert_eq!(Metric::Horizontal.distance(&a, &b), 5.0);
+        assert_eq!(Metric::Manhattan.distance(&a, &b), 19.0);
+        assert_eq!(Metric::Chebyshev.distance(&a, &b), 12.0);
+    }
+
+    #[test]
+    fn nether_results_measured_from_the_overworld() {
+        let origin = Origin {
+            point: Point {
+                x: 800,
+                y: 64,
+                z: -800,
+            },
+            scale: Scale::Overworld,
+            dimension: Scale::Nether,
+            metric: Metric::Horizontal,
+        };
+
+        assert_eq!(
+            origin.local(),
+            Point {
+                x: 100,
+                y: 64,
+                z: -100
+            }
+        );
+        assert_eq!(
+            origin.distance_to(&Point {
+                x: 100,
+                y: 30,
+                z: -90
+            }),
+            80.0
+        );
+
+        // A threshold of 100 Overworld blocks only spans 13 Nether blocks
+        let threshold = Point {
+            x: 100,
+            y: 16,
+            z: 8,
+        };
+        assert_eq!(
+            origin.local_extent(&threshold),
+            Point { x: 13, y: 16, z: 1 }
+        );
+
+        let from_nether = Origin {
+            scale: Scale::Nether,
+            dimension: Scale::Overworld,
+            ..origin
+        };
+        assert_eq!(
+            from_nether.local_extent(&threshold),
+            Point {
+                x: 800,
+                y: 16,
+                z: 64
+            }
+        );
+
+        // Overworld -> Nether rounds towards negative infinity like the game does
+        let point = Point { x: -1, y: 0, z: 15 };
+        assert_eq!(
+            point.rescale(Scale::Overworld, Scale::Nether),
+            Point { x: -1, y: 0, z: 1 }
+        );
+    }
+}
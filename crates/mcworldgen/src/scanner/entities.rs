@@ -0,0 +1,306 @@
+use std::{io, path::Path};
+
+use fastnbt::Value;
+use serde::{de::DeserializeOwned, Deserialize};
+use wildmatch::WildMatch;
+
+use super::{
+    blocks::{empty_compound, read_nbt, BlockPatterns},
+    items::{text_of, Item, ItemQuery},
+    points::Point,
+    scan::{read_chunks, scan_region_files},
+    shapes::Shape,
+};
+
+/// Kept as raw NBT so one unusual entity doesn't lose the whole chunk's
+#[derive(Deserialize, Debug)]
+struct EntityChunk {
+    #[serde(rename = "Entities", default)]
+    entities: Vec<Value>,
+}
+
+/// Read every entity in the area of a dimension and collect what `read` makes of them
+///
+/// Since 1.17 entities are kept in their own region files; older worlds have none.
+/// Entities riding others, e.g. villagers in boats, are read along with their vehicle.
+pub fn scan_entities<E, T, F>(path: &Path, area: &dyn Shape, read: F) -> io::Result<Vec<T>>
+where
+    E: DeserializeOwned,
+    T: Send,
+    F: Fn(E) -> Option<T> + Send + Sync,
+{
+    let dir = path.join("entities");
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let (found, _) = scan_region_files(&dir, area, |region, rx, rz| {
+        read_chunks(region, rx, rz, area, |chunk: EntityChunk| {
+            let mut entities = vec![];
+
+            for entity in chunk.entities {
+                push_with_passengers(entity, &mut entities);
+            }
+
+            entities
+                .into_iter()
+                .filter_map(|nbt| read_nbt::<E>(&nbt))
+                .filter_map(&read)
+                .collect()
+        })
+    })?;
+
+    Ok(found.into_iter().flatten().collect())
+}
+
+// Riders are kept in their vehicle's `Passengers` and may carry passengers of their own
+fn push_with_passengers(entity: Value, entities: &mut Vec<Value>) {
+    let passengers = match &entity {
+        Value::Compound(map) => match map.get("Passengers") {
+            Some(Value::List(passengers)) => passengers.clone(),
+            _ => vec![],
+        },
+        _ => vec![],
+    };
+
+    entities.push(entity);
+
+    for passenger in passengers {
+        push_with_passengers(passenger, entities);
+    }
+}
+
+/// The block an entity's `Pos` is in
+pub fn position_of(pos: &[f64]) -> Option<Point> {
+    match pos {
+        [x, y, z] => Some(Point {
+            x: x.floor() as i64,
+            y: y.floor() as i64,
+            z: z.floor() as i64,
+        }),
+        _ => None,
+    }
+}
+
+/// A mob, pet, villager or other entity
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Entity {
+    pub id: String,
+
+    #[serde(rename = "Pos")]
+    pos: Vec<f64>,
+
+    #[serde(rename = "CustomName")]
+    custom_name: Option<Value>,
+
+    /// An array of four ints since 1.16, a string before
+    #[serde(rename = "Owner")]
+    owner: Option<Value>,
+
+    #[serde(rename = "OwnerUUID")]
+    old_owner: Option<String>,
+
+    #[serde(rename = "VillagerData")]
+    pub villager: Option<VillagerData>,
+
+    #[serde(rename = "Offers")]
+    offers: Option<Offers>,
+
+    /// Everything the entity stores, including the fields above
+    #[serde(skip, default = "empty_compound")]
+    pub nbt: Value,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct VillagerData {
+    pub profession: Option<String>,
+    pub level: Option<i32>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct Offers {
+    #[serde(rename = "Recipes", default)]
+    recipes: Vec<Trade>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Trade {
+    pub buy: Option<Item>,
+
+    #[serde(rename = "buyB")]
+    pub buy_b: Option<Item>,
+
+    pub sell: Option<Item>,
+}
+
+impl Entity {
+    /// Read the known fields of an entity while keeping all of its NBT
+    pub fn from_nbt(nbt: Value) -> Option<Self> {
+        let mut entity: Self = read_nbt(&nbt)?;
+        entity.nbt = nbt;
+
+        Some(entity)
+    }
+
+    pub fn position(&self) -> Option<Point> {
+        position_of(&self.pos)
+    }
+
+    pub fn custom_name(&self) -> Option<String> {
+        self.custom_name.as_ref().map(text_of)
+    }
+
+    /// The UUID of the player that tamed the entity, as it's written in player data file names
+    pub fn owner(&self) -> Option<String> {
+        match &self.owner {
+            Some(Value::IntArray(ints)) => format_uuid(ints),
+            Some(Value::String(uuid)) => Some(uuid.clone()),
+            _ => self.old_owner.clone(),
+        }
+    }
+
+    pub fn trades(&self) -> &[Trade] {
+        self.offers.as_ref().map_or(&[], |offers| &offers.recipes)
+    }
+}
+
+fn format_uuid(ints: &[i32]) -> Option<String> {
+    let [a, b, c, d] = ints else {
+        return None;
+    };
+
+    let hex = format!(
+        "{:08x}{:08x}{:08x}{:08x}",
+        *a as u32, *b as u32, *c as u32, *d as u32
+    );
+
+    Some(format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    ))
+}
+
+/// What to look for among the entities
+pub struct EntityQuery {
+    pub ids: BlockPatterns,
+
+    /// Matched against the custom name, ignoring case
+    pub name: Option<WildMatch>,
+    pub owner: Option<String>,
+    pub profession: Option<WildMatch>,
+    pub min_level: Option<i32>,
+
+    /// Only entities with a trade selling a matching item
+    pub sells: Option<ItemQuery>,
+}
+
+impl EntityQuery {
+    pub fn matches(&self, entity: &Entity) -> bool {
+        let villager = entity.villager.as_ref();
+
+        self.ids.matches(&entity.id)
+            && self.name.as_ref().is_none_or(|name| {
+                entity
+                    .custom_name()
+                    .is_some_and(|custom| name.matches(&custom.to_lowercase()))
+            })
+            && self.owner.as_ref().is_none_or(|owner| {
+                entity
+                    .owner()
+                    .is_some_and(|uuid| uuid.eq_ignore_ascii_case(owner))
+            })
+            && self.profession.as_ref().is_none_or(|profession| {
+                villager
+                    .and_then(|villager| villager.profession.as_ref())
+                    .is_some_and(|id| profession.matches(id))
+            })
+            && self.min_level.is_none_or(|min| {
+                villager
+                    .and_then(|villager| villager.level)
+                    .is_some_and(|level| level >= min)
+            })
+            && self.sells.as_ref().is_none_or(|sells| {
+                entity
+                    .trades()
+                    .iter()
+                    .filter_map(|trade| trade.sell.as_ref())
+                    .any(|item| sells.matches(item))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fastnbt::nbt;
+
+    use super::*;
+
+    #[test]
+    fn villagers_riding_boats_are_found() {
+        let boat = nbt!({
+            "id": "minecraft:oak_boat",
+            "Pos": [10.5, 64.0, 3.5],
+            "Passengers": [{
+                "id": "minecraft:villager",
+                "Pos": [10.5, 64.2, 3.5],
+                "VillagerData": {"profession": "minecraft:librarian", "level": 3},
+                "Offers": {"Recipes": [{
+                    "buy": {"id": "minecraft:emerald", "count": 20},
+                    "sell": {
+                        "id": "minecraft:enchanted_book",
+                        "count": 1,
+                        "components": {"minecraft:stored_enchantments": {"minecraft:mending": 1}}
+                    }
+                }]}
+            }]
+        });
+
+        let mut entities = vec![];
+        push_with_passengers(boat, &mut entities);
+
+        let entities = entities
+            .into_iter()
+            .filter_map(Entity::from_nbt)
+            .collect::<Vec<_>>();
+        assert_eq!(entities.len(), 2);
+
+        let villager = &entities[1];
+        assert_eq!(villager.position(), Some(Point { x: 10, y: 64, z: 3 }));
+
+        let query = |profession: &str, min_level, sells: &str| EntityQuery {
+            ids: BlockPatterns::parse("minecraft:villager"),
+            name: None,
+            owner: None,
+            profession: Some(WildMatch::new(profession)),
+            min_level,
+            sells: Some(ItemQuery {
+                ids: BlockPatterns::parse(sells),
+                name: None,
+                enchantment: Some((WildMatch::new("*mending"), 1)),
+            }),
+        };
+
+        assert!(query("*librarian", Some(3), "*book").matches(villager));
+        assert!(!query("*librarian", Some(3), "*book").matches(&entities[0]));
+        assert!(!query("*cleric", None, "*book").matches(villager));
+        assert!(!query("*librarian", Some(4), "*book").matches(villager));
+        assert!(!query("*librarian", None, "*emerald").matches(villager));
+    }
+
+    #[test]
+    fn owners_are_formatted_like_player_files() {
+        assert_eq!(
+            format_uuid(&[0x0b5e1c2a, 0x11112222, 0x33334444, 0x55556666]).as_deref(),
+            Some("0b5e1c2a-1111-2222-3333-444455556666")
+        );
+        assert_eq!(
+            format_uuid(&[-1, 0, 0, 1]).as_deref(),
+            Some("ffffffff-0000-0000-0000-000000000001")
+        );
+        assert_eq!(format_uuid(&[1, 2]), None);
+    }
+}
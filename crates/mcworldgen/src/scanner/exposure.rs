@@ -0,0 +1,169 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+use itertools::Itertools;
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+
+use super::{
+    blocks::BlockDescriptor,
+    lookup::BlockLookup,
+    points::{Connectivity, Point},
+    veins::Vein,
+};
+
+/// How many faces of a block touch air, water or lava
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Exposure {
+    /// Faces touching air, cave air or void air
+    pub air: usize,
+    pub water: usize,
+    pub lava: usize,
+}
+
+impl Exposure {
+    pub fn faces(&self) -> usize {
+        self.air + self.water + self.lava
+    }
+
+    pub fn is_exposed(&self) -> bool {
+        self.faces() > 0
+    }
+
+    fn add(&mut self, name: &str) {
+        match name {
+            "minecraft:air" | "minecraft:cave_air" | "minecraft:void_air" => self.air += 1,
+            "minecraft:water" => self.water += 1,
+            "minecraft:lava" => self.lava += 1,
+            _ => {}
+        }
+    }
+}
+
+/// The exposure of every block, keyed by position
+///
+/// Neighbors are read from the world, so faces at chunk and region borders
+/// are checked too. Faces next to chunks that haven't been generated don't count.
+pub fn find_exposure(path: &Path, blocks: &[BlockDescriptor]) -> HashMap<Point, Exposure> {
+    // Blocks in the same region share a lookup (and its cache)
+    let by_region = blocks
+        .iter()
+        .map(BlockDescriptor::pos)
+        .into_group_map_by(|pos| (pos.x.div_euclid(512), pos.z.div_euclid(512)));
+
+    by_region
+        .into_values()
+        .collect_vec()
+        .into_par_iter()
+        .flat_map_iter(|points| {
+            let mut lookup = BlockLookup::new(path);
+
+            points
+                .into_iter()
+                .map(|point| {
+                    let exposure = exposure_at(&mut lookup, &point);
+                    (point, exposure)
+                })
+                .collect_vec()
+        })
+        .collect()
+}
+
+/// The exposure of the block at a position
+fn exposure_at(lookup: &mut BlockLookup, point: &Point) -> Exposure {
+    let mut exposure = Exposure::default();
+
+    for neighbor in point.neighbors(Connectivity::Faces, 1) {
+        if let Some(name) = lookup.block_at(&neighbor) {
+            exposure.add(name);
+        }
+    }
+
+    exposure
+}
+
+/// The exposure of a whole vein along with how many of its blocks are exposed
+pub fn vein_exposure(vein: &Vein, exposure: &HashMap<Point, Exposure>) -> (usize, Exposure) {
+    let mut total = Exposure::default();
+    let mut exposed = 0;
+
+    for block in &vein.blocks {
+        let Some(block) = exposure.get(&block.pos()) else {
+            continue;
+        };
+
+        total.air += block.air;
+        total.water += block.water;
+        total.lava += block.lava;
+        exposed += block.is_exposed() as usize;
+    }
+
+    (exposed, total)
+}
+
+/// The share of blocks at each Y level with at least one exposed face
+pub struct ExposureLevel {
+    pub blocks: usize,
+    pub exposed: usize,
+}
+
+pub fn exposure_by_y(exposure: &HashMap<Point, Exposure>) -> BTreeMap<i64, ExposureLevel> {
+    let mut levels = BTreeMap::<i64, ExposureLevel>::new();
+
+    for (point, exposure) in exposure {
+        let level = levels.entry(point.y).or_insert(ExposureLevel {
+            blocks: 0,
+            exposed: 0,
+        });
+
+        level.blocks += 1;
+        level.exposed += exposure.is_exposed() as usize;
+    }
+
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::veins::{find_veins, Linking};
+
+    #[test]
+    fn faces_touching_caves_and_fluids() {
+        // Two diamonds side by side, one with cave air above it and water to its side
+        let mut lookup = BlockLookup::new(Path::new("world"));
+        lookup.set_section((0, 0, 0), |point| match (point.x, point.y, point.z) {
+            (4 | 5, 4, 4) => ("minecraft:diamond_ore", 0),
+            (4, 5, 4) => ("minecraft:cave_air", 0),
+            (3, 4, 4) => ("minecraft:water", 0),
+            _ => ("minecraft:stone", 0),
+        });
+
+        let blocks = vec![
+            BlockDescriptor::new("minecraft:diamond_ore", 4, 4, 4),
+            BlockDescriptor::new("minecraft:diamond_ore", 5, 4, 4),
+        ];
+        let exposure = blocks
+            .iter()
+            .map(|block| (block.pos(), exposure_at(&mut lookup, &block.pos())))
+            .collect::<HashMap<_, _>>();
+
+        assert_eq!(
+            exposure[&Point { x: 4, y: 4, z: 4 }],
+            Exposure {
+                air: 1,
+                water: 1,
+                lava: 0
+            }
+        );
+        assert!(!exposure[&Point { x: 5, y: 4, z: 4 }].is_exposed());
+
+        let vein = find_veins(blocks, Linking::default()).next().unwrap();
+        let (exposed, faces) = vein_exposure(&vein, &exposure);
+        assert_eq!((exposed, faces.faces()), (1, 2));
+
+        let level = &exposure_by_y(&exposure)[&4];
+        assert_eq!((level.blocks, level.exposed), (2, 1));
+    }
+}
@@ -0,0 +1,335 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io,
+    path::Path,
+};
+
+use fastanvil::{JavaChunk, Region};
+use itertools::Itertools;
+
+use super::{
+    blocks::BlockPatterns,
+    bounds::Bounds,
+    census::section_pos,
+    points::{Connectivity, Point},
+    scan::scan_regions,
+    shapes::Shape,
+};
+
+const WATER: &str = "minecraft:water";
+
+/// Blocks that are always full of water, without a `waterlogged` state
+const WATER_PLANTS: [&str; 5] = [
+    "minecraft:bubble_column",
+    "minecraft:kelp",
+    "minecraft:kelp_plant",
+    "minecraft:seagrass",
+    "minecraft:tall_seagrass",
+];
+
+/// The fluid level of a block state like `minecraft:water|level=0`
+///
+/// 0 is a source block, 1 to 7 flow further away from it and 8 and up are falling.
+pub fn fluid_level(state: &str) -> Option<u8> {
+    property(state, "level").and_then(|level| level.parse().ok())
+}
+
+fn property<'a>(state: &'a str, name: &str) -> Option<&'a str> {
+    let (_, properties) = state.split_once('|')?;
+
+    properties.split(',').find_map(|property| {
+        property
+            .strip_prefix(name)
+            .and_then(|rest| rest.strip_prefix('='))
+    })
+}
+
+/// The fluid a block state holds and whether it's a source, if it matches the patterns
+///
+/// Waterlogged blocks and water plants count as water sources when the patterns match water.
+pub fn fluid_of<'a>(patterns: &BlockPatterns, state: &'a str) -> Option<(&'a str, bool)> {
+    let name = state.split('|').next().unwrap_or(state);
+
+    if patterns.matches(name) {
+        return Some((name, fluid_level(state).unwrap_or(0) == 0));
+    }
+
+    let watery = WATER_PLANTS.contains(&name) || property(state, "waterlogged") == Some("true");
+
+    (watery && patterns.matches(WATER)).then_some((WATER, true))
+}
+
+/// A block of fluid, with its fluid as an index into the names of a `FluidScan`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FluidBlock {
+    pub pos: Point,
+    pub fluid: u16,
+    pub source: bool,
+}
+
+/// The fluid blocks in part of a dimension
+///
+/// Ocean-sized scans find millions of blocks, so each fluid's name is only kept once.
+#[derive(Debug, Default)]
+pub struct FluidScan {
+    pub fluids: Vec<String>,
+    pub blocks: Vec<FluidBlock>,
+}
+
+impl FluidScan {
+    /// The index of a fluid's name, adding it if it's new
+    fn fluid_index(&mut self, fluid: &str) -> u16 {
+        match self.fluids.iter().position(|known| known == fluid) {
+            Some(index) => index as u16,
+            None => {
+                self.fluids.push(fluid.to_string());
+                (self.fluids.len() - 1) as u16
+            }
+        }
+    }
+
+    fn combine(scans: impl Iterator<Item = Self>) -> Self {
+        let mut combined = Self::default();
+
+        for scan in scans {
+            let indices = scan
+                .fluids
+                .iter()
+                .map(|fluid| combined.fluid_index(fluid))
+                .collect_vec();
+
+            combined
+                .blocks
+                .extend(scan.blocks.into_iter().map(|block| FluidBlock {
+                    fluid: indices[block.fluid as usize],
+                    ..block
+                }));
+        }
+
+        combined
+    }
+}
+
+/// Find the fluid blocks matching the patterns inside the area of a dimension
+pub fn locate_fluids(
+    path: &Path,
+    area: &dyn Shape,
+    patterns: &BlockPatterns,
+) -> io::Result<FluidScan> {
+    let (found, _) = scan_regions(path, area, |region, rx, rz| {
+        fluids_in_region(patterns, area, region, rx, rz)
+    })?;
+
+    Ok(FluidScan::combine(found.into_iter()))
+}
+
+fn fluids_in_region(
+    patterns: &BlockPatterns,
+    area: &dyn Shape,
+    region: &mut Region<File>,
+    rx: i64,
+    rz: i64,
+) -> FluidScan {
+    let mut found = FluidScan::default();
+
+    for (x, z) in (0..32i64).flat_map(|z| (0..32i64).map(move |x| (x, z))) {
+        let (cx, cz) = (rx * 32 + x, rz * 32 + z);
+
+        if !area.intersects(&Bounds::of_chunk(cx, cz)) {
+            continue;
+        }
+
+        let tower = match region.read_chunk(x as usize, z as usize) {
+            Ok(Some(data)) => match JavaChunk::from_bytes(&data) {
+                Ok(JavaChunk::Post18(chunk)) => chunk.sections,
+                _ => None,
+            },
+            _ => None,
+        };
+
+        let Some(tower) = tower else {
+            continue;
+        };
+
+        for section in tower.sections() {
+            let bounds = Bounds::of_section(cx, section.y as i64, cz);
+
+            if !area.intersects(&bounds) {
+                continue;
+            }
+
+            let palette = section.block_states.palette();
+            let fluids = palette
+                .iter()
+                .map(|block| {
+                    let state = block.encoded_description();
+                    let (fluid, source) = fluid_of(patterns, state)?;
+
+                    Some((found.fluid_index(fluid), source))
+                })
+                .collect_vec();
+
+            if fluids.iter().all(Option::is_none) {
+                continue;
+            }
+
+            // Oceans and lava lakes fill whole sections, which don't store any indices
+            let indices: Box<dyn Iterator<Item = usize>> =
+                match section.block_states.try_iter_indices() {
+                    Some(iter) => Box::new(iter),
+                    None => Box::new(std::iter::repeat_n(0, 4096)),
+                };
+
+            for (i, index) in indices.enumerate() {
+                let Some((fluid, source)) = fluids[index] else {
+                    continue;
+                };
+
+                let pos = section_pos(&bounds, i);
+                if area.contains(&pos) {
+                    found.blocks.push(FluidBlock { pos, fluid, source });
+                }
+            }
+        }
+    }
+
+    found
+}
+
+/// A connected body of one fluid
+pub struct FluidBody {
+    pub fluid: String,
+    pub bounds: Bounds,
+    pub center: Point,
+    pub volume: usize,
+
+    /// Blocks with `level=0` or no level at all, including waterlogged blocks
+    pub sources: usize,
+
+    /// Faces of the body that touch anything other than the same fluid
+    pub surface: usize,
+
+    /// Blocks without the same fluid above them, i.e. the area of the body's top
+    pub open_top: usize,
+}
+
+/// Flood fill fluid blocks into connected bodies, linked through their faces
+pub fn find_fluid_bodies(scan: FluidScan) -> Vec<FluidBody> {
+    let blocks = scan
+        .blocks
+        .into_iter()
+        .map(|block| (block.pos, (block.fluid, block.source)))
+        .collect::<HashMap<_, _>>();
+
+    let mut filled = HashSet::new();
+    let mut bodies = vec![];
+
+    for (start, (fluid, _)) in &blocks {
+        if !filled.insert(start.clone()) {
+            continue;
+        }
+
+        let same_fluid = |pos: &Point| blocks.get(pos).is_some_and(|(other, _)| other == fluid);
+
+        let mut body = FluidBody {
+            fluid: scan.fluids[*fluid as usize].clone(),
+            bounds: Bounds::from_point(start),
+            center: start.clone(),
+            volume: 0,
+            sources: 0,
+            surface: 0,
+            open_top: 0,
+        };
+        let mut queue = vec![start.clone()];
+
+        while let Some(pos) = queue.pop() {
+            body.volume += 1;
+            body.sources += blocks[&pos].1 as usize;
+            body.bounds = body.bounds.union(&Bounds::from_point(&pos));
+
+            let above = Point {
+                y: pos.y + 1,
+                ..pos.clone()
+            };
+            body.open_top += !same_fluid(&above) as usize;
+
+            for neighbor in pos.neighbors(Connectivity::Faces, 1) {
+                if !same_fluid(&neighbor) {
+                    body.surface += 1;
+                } else if filled.insert(neighbor.clone()) {
+                    queue.push(neighbor);
+                }
+            }
+        }
+
+        body.center = body.bounds.center();
+        bodies.push(body);
+    }
+
+    bodies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waterlogged_blocks_are_water_sources() {
+        let water = BlockPatterns::parse("minecraft:water");
+        let lava = BlockPatterns::parse("minecraft:lava");
+
+        assert_eq!(
+            fluid_of(&water, "minecraft:water|level=3"),
+            Some((WATER, false))
+        );
+        assert_eq!(
+            fluid_of(&water, "minecraft:oak_stairs|facing=east,waterlogged=true"),
+            Some((WATER, true))
+        );
+        assert_eq!(fluid_of(&water, "minecraft:seagrass"), Some((WATER, true)));
+        assert_eq!(
+            fluid_of(&water, "minecraft:oak_stairs|facing=east,waterlogged=false"),
+            None
+        );
+        assert_eq!(fluid_of(&lava, "minecraft:kelp|age=3"), None);
+        assert_eq!(
+            fluid_of(&lava, "minecraft:lava|level=0"),
+            Some(("minecraft:lava", true))
+        );
+    }
+
+    #[test]
+    fn sources_and_flowing_blocks_form_one_body() {
+        assert_eq!(fluid_level("minecraft:lava|level=0"), Some(0));
+        assert_eq!(fluid_level("minecraft:water|falling=true,level=9"), Some(9));
+        assert_eq!(fluid_level("minecraft:water"), None);
+
+        let mut scan = FluidScan::default();
+        for (fluid, source, x) in [
+            ("minecraft:lava", true, 0),
+            ("minecraft:lava", true, 1),
+            ("minecraft:lava", false, 2),
+            ("minecraft:water", true, 3),
+        ] {
+            let fluid = scan.fluid_index(fluid);
+            let pos = Point { x, y: 0, z: 0 };
+            scan.blocks.push(FluidBlock { pos, fluid, source });
+        }
+
+        let bodies = find_fluid_bodies(scan);
+
+        let lava = bodies
+            .iter()
+            .find(|body| body.fluid == "minecraft:lava")
+            .unwrap();
+        assert_eq!(lava.volume, 3);
+        assert_eq!(lava.sources, 2);
+        assert_eq!(lava.center, Point { x: 1, y: 0, z: 0 });
+
+        // A 3x1x1 bar has 14 faces, all of them open
+        assert_eq!(lava.surface, 14);
+        assert_eq!(lava.open_top, 3);
+        assert_eq!(bodies.len(), 2);
+    }
+}
@@ -0,0 +1,171 @@
+use std::fmt;
+
+use wildmatch::WildMatch;
+
+use super::blocks::{normalize_name, BlockDescriptor};
+
+/// The ore tags from the vanilla data pack, which can be used as group members e.g. `#minecraft:copper_ores`
+const VANILLA_TAGS: [(&str, &[&str]); 8] = [
+    (
+        "minecraft:coal_ores",
+        &["minecraft:coal_ore", "minecraft:deepslate_coal_ore"],
+    ),
+    (
+        "minecraft:copper_ores",
+        &["minecraft:copper_ore", "minecraft:deepslate_copper_ore"],
+    ),
+    (
+        "minecraft:diamond_ores",
+        &["minecraft:diamond_ore", "minecraft:deepslate_diamond_ore"],
+    ),
+    (
+        "minecraft:emerald_ores",
+        &["minecraft:emerald_ore", "minecraft:deepslate_emerald_ore"],
+    ),
+    (
+        "minecraft:gold_ores",
+        &[
+            "minecraft:gold_ore",
+            "minecraft:deepslate_gold_ore",
+            "minecraft:nether_gold_ore",
+        ],
+    ),
+    (
+        "minecraft:iron_ores",
+        &["minecraft:iron_ore", "minecraft:deepslate_iron_ore"],
+    ),
+    (
+        "minecraft:lapis_ores",
+        &["minecraft:lapis_ore", "minecraft:deepslate_lapis_ore"],
+    ),
+    (
+        "minecraft:redstone_ores",
+        &["minecraft:redstone_ore", "minecraft:deepslate_redstone_ore"],
+    ),
+];
+
+#[derive(Debug, PartialEq)]
+pub struct GroupParseError(String);
+
+impl fmt::Display for GroupParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid block group: {}", self.0)
+    }
+}
+
+/// A named set of blocks that are treated as the same block when forming veins
+pub struct BlockGroup {
+    pub name: String,
+    members: Vec<WildMatch>,
+}
+
+impl BlockGroup {
+    /// Parse `name=member,member`, where a member is a wildcard pattern or a `#tag`
+    pub fn parse(definition: &str) -> Result<Self, GroupParseError> {
+        let (name, members) = definition
+            .split_once('=')
+            .ok_or_else(|| GroupParseError(format!("expected name=blocks in '{}'", definition)))?;
+
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(GroupParseError(format!("missing name in '{}'", definition)));
+        }
+
+        let mut patterns = vec![];
+
+        for member in members.split(',').map(str::trim).filter(|m| !m.is_empty()) {
+            match member.strip_prefix('#') {
+                Some(tag) => {
+                    let (_, blocks) = VANILLA_TAGS
+                        .iter()
+                        .find(|(name, _)| *name == tag)
+                        .ok_or_else(|| GroupParseError(format!("unknown tag '{}'", member)))?;
+
+                    patterns.extend(blocks.iter().map(|block| WildMatch::new(block)));
+                }
+                None => patterns.push(WildMatch::new(member)),
+            }
+        }
+
+        if patterns.is_empty() {
+            return Err(GroupParseError(format!("no blocks in '{}'", definition)));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            members: patterns,
+        })
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.members.iter().any(|member| member.matches(name))
+    }
+}
+
+/// User defined block groups, checked in order
+///
+/// Blocks that aren't in any group fall back to their normalized name.
+#[derive(Default)]
+pub struct BlockGroups(pub Vec<BlockGroup>);
+
+impl BlockGroups {
+    /// Parse a group file with one `name=member,member` definition per line
+    ///
+    /// Empty lines and lines starting with `# ` are ignored.
+    pub fn parse(text: &str) -> Result<Self, GroupParseError> {
+        text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && *line != "#" && !line.starts_with("# "))
+            .map(BlockGroup::parse)
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self)
+    }
+
+    pub fn group_of(&self, name: &str) -> String {
+        self.0
+            .iter()
+            .find(|group| group.contains(name))
+            .map_or_else(|| normalize_name(name), |group| group.name.clone())
+    }
+
+    /// Put each block into its group
+    pub fn assign(&self, blocks: Vec<BlockDescriptor>) -> Vec<BlockDescriptor> {
+        if self.0.is_empty() {
+            return blocks;
+        }
+
+        blocks
+            .into_iter()
+            .map(|block| BlockDescriptor {
+                group: self.group_of(&block.name),
+                ..block
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups() {
+        let groups = BlockGroups::parse(
+            "# copper veins\n\
+             copper = #minecraft:copper_ores, minecraft:raw_copper_block\n\
+             stone=minecraft:*stone\n",
+        )
+        .unwrap();
+
+        assert_eq!(groups.group_of("minecraft:deepslate_copper_ore"), "copper");
+        assert_eq!(groups.group_of("minecraft:raw_copper_block"), "copper");
+        assert_eq!(groups.group_of("minecraft:blackstone"), "stone");
+        assert_eq!(
+            groups.group_of("minecraft:deepslate_iron_ore"),
+            "minecraft:iron_ore"
+        );
+
+        assert!(BlockGroup::parse("ores=#minecraft:ores").is_err());
+        assert!(BlockGroup::parse("minecraft:coal_ore").is_err());
+    }
+}
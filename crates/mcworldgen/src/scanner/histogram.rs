@@ -0,0 +1,398 @@
+use std::{collections::BTreeMap, io, str::FromStr};
+
+use itertools::Itertools;
+
+use super::{blocks::BlockDescriptor, scan::ScanResult};
+
+/// What the number of matches at each Y level is divided by
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Normalization {
+    /// Matches per scanned chunk
+    Chunk,
+
+    /// Matches per 1000 non-air blocks scanned at the same Y level
+    Blocks,
+}
+
+impl Normalization {
+    fn label(&self) -> &'static str {
+        match self {
+            Normalization::Chunk => "per chunk",
+            Normalization::Blocks => "per 1000 blocks",
+        }
+    }
+}
+
+impl FromStr for Normalization {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "chunk" => Ok(Normalization::Chunk),
+            "blocks" => Ok(Normalization::Blocks),
+            _ => Err(format!("Unknown normalization '{}'", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HistogramFormat {
+    Table,
+    Csv,
+    Json,
+
+    /// A bar chart drawn with unicode block elements
+    Chart,
+
+    /// A bar chart drawn with plain ASCII
+    Ascii,
+}
+
+impl FromStr for HistogramFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(HistogramFormat::Table),
+            "csv" => Ok(HistogramFormat::Csv),
+            "json" => Ok(HistogramFormat::Json),
+            "chart" => Ok(HistogramFormat::Chart),
+            "ascii" => Ok(HistogramFormat::Ascii),
+            _ => Err(format!("Unknown histogram format '{}'", s)),
+        }
+    }
+}
+
+/// The number of blocks matching a pattern at each Y level
+pub struct YHistogram {
+    pub pattern: String,
+    pub counts: BTreeMap<i64, usize>,
+}
+
+impl YHistogram {
+    pub fn from_blocks<'a>(
+        pattern: &str,
+        blocks: impl Iterator<Item = &'a BlockDescriptor>,
+    ) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            counts: blocks.map(|block| block.y).counts().into_iter().collect(),
+        }
+    }
+
+    /// Every Y level between the lowest and highest match, including empty ones
+    pub fn levels(&self) -> std::ops::RangeInclusive<i64> {
+        let min = self.counts.keys().next().copied().unwrap_or(0);
+        let max = self.counts.keys().last().copied().unwrap_or(-1);
+
+        min..=max
+    }
+
+    pub fn count(&self, y: i64) -> usize {
+        self.counts.get(&y).copied().unwrap_or(0)
+    }
+
+    pub fn rate(&self, y: i64, scan: &ScanResult, per: Normalization) -> f64 {
+        let count = self.count(y) as f64;
+
+        let total = match per {
+            Normalization::Chunk => scan.chunks as f64,
+            Normalization::Blocks => scan.blocks_by_y.get(&y).copied().unwrap_or(0) as f64 / 1000.0,
+        };
+
+        if total > 0.0 {
+            count / total
+        } else {
+            0.0
+        }
+    }
+
+    /// The Y level with the highest rate of matches
+    pub fn best_y(&self, scan: &ScanResult, per: Normalization) -> Option<(i64, f64)> {
+        self.counts
+            .keys()
+            .map(|y| (*y, self.rate(*y, scan, per)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+pub fn write_histograms(
+    out: &mut impl io::Write,
+    histograms: &[YHistogram],
+    scan: &ScanResult,
+    per: Normalization,
+    format: HistogramFormat,
+) -> io::Result<()> {
+    match format {
+        HistogramFormat::Table => write_tables(out, histograms, scan, per),
+        HistogramFormat::Csv => write_csv(out, histograms, scan, per),
+        HistogramFormat::Json => write_json(out, histograms, scan, per),
+        HistogramFormat::Chart => write_charts(out, histograms, scan, per, true),
+        HistogramFormat::Ascii => write_charts(out, histograms, scan, per, false),
+    }
+}
+
+fn write_best_y(
+    out: &mut impl io::Write,
+    histogram: &YHistogram,
+    scan: &ScanResult,
+    per: Normalization,
+) -> io::Result<()> {
+    match histogram.best_y(scan, per) {
+        Some((y, rate)) => writeln!(
+            out,
+            "Best Y for {}: {} ({:.3} {})",
+            histogram.pattern,
+            y,
+            rate,
+            per.label()
+        ),
+        None => writeln!(out, "Best Y for {}: no matches", histogram.pattern),
+    }
+}
+
+fn write_tables(
+    out: &mut impl io::Write,
+    histograms: &[YHistogram],
+    scan: &ScanResult,
+    per: Normalization,
+) -> io::Result<()> {
+    for histogram in histograms {
+        writeln!(out, "Y distribution of {}", histogram.pattern)?;
+        writeln!(out, "{:>6} {:>8} {:>16}", "y", "count", per.label())?;
+
+        for y in histogram.levels().rev() {
+            writeln!(
+                out,
+                "{:>6} {:>8} {:>16.3}",
+                y,
+                histogram.count(y),
+                histogram.rate(y, scan, per)
+            )?;
+        }
+
+        write_best_y(out, histogram, scan, per)?;
+    }
+
+    Ok(())
+}
+
+fn write_csv(
+    out: &mut impl io::Write,
+    histograms: &[YHistogram],
+    scan: &ScanResult,
+    per: Normalization,
+) -> io::Result<()> {
+    writeln!(out, "pattern,y,count,rate")?;
+
+    for histogram in histograms {
+        for y in histogram.levels() {
+            writeln!(
+                out,
+                "{},{},{},{}",
+                histogram.pattern,
+                y,
+                histogram.count(y),
+                histogram.rate(y, scan, per)
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_json(
+    out: &mut impl io::Write,
+    histograms: &[YHistogram],
+    scan: &ScanResult,
+    per: Normalization,
+) -> io::Result<()> {
+    let entries = histograms
+        .iter()
+        .map(|histogram| {
+            let levels = histogram
+                .levels()
+                .map(|y| {
+                    format!(
+                        "{{\"y\":{},\"count\":{},\"rate\":{}}}",
+                        y,
+                        histogram.count(y),
+                        histogram.rate(y, scan, per)
+                    )
+                })
+                .join(",");
+
+            let best_y = histogram
+                .best_y(scan, per)
+                .map_or("null".to_string(), |(y, _)| y.to_string());
+
+            format!(
+                "{{\"pattern\":{},\"normalization\":{},\"best_y\":{},\"levels\":[{}]}}",
+                json_string(&histogram.pattern),
+                json_string(per.label()),
+                best_y,
+                levels
+            )
+        })
+        .join(",");
+
+    writeln!(out, "[{}]", entries)
+}
+
+/// A string as a quoted JSON string
+fn json_string(text: &str) -> String {
+    let mut quoted = String::from('"');
+
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+const CHART_WIDTH: f64 = 50.0;
+const EIGHTHS: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+
+fn write_charts(
+    out: &mut impl io::Write,
+    histograms: &[YHistogram],
+    scan: &ScanResult,
+    per: Normalization,
+    unicode: bool,
+) -> io::Result<()> {
+    for histogram in histograms {
+        let max = histogram
+            .levels()
+            .map(|y| histogram.rate(y, scan, per))
+            .fold(0.0, f64::max);
+
+        writeln!(
+            out,
+            "Y distribution of {} ({})",
+            histogram.pattern,
+            per.label()
+        )?;
+
+        for y in histogram.levels().rev() {
+            let rate = histogram.rate(y, scan, per);
+            let width = if max > 0.0 {
+                rate / max * CHART_WIDTH
+            } else {
+                0.0
+            };
+
+            let bar = match unicode {
+                true => {
+                    let eighths = (width * 8.0).round() as usize;
+                    "█".repeat(eighths / 8) + EIGHTHS[eighths % 8]
+                }
+                false => "#".repeat(width.round() as usize),
+            };
+
+            let axis = if unicode { "│" } else { "|" };
+
+            writeln!(out, "{:>6} {}{} {:.3}", y, axis, bar, rate)?;
+        }
+
+        write_best_y(out, histogram, scan, per)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn histogram() -> (YHistogram, ScanResult) {
+        let blocks = [(-59, 8), (-58, 2), (12, 1)]
+            .iter()
+            .flat_map(|(y, count)| {
+                (0..*count).map(|x| BlockDescriptor::new("minecraft:diamond_ore", x, *y, 0))
+            })
+            .collect_vec();
+
+        let mut scan = ScanResult::new();
+        scan.chunks = 4;
+        scan.blocks_by_y.insert(-59, 4000);
+        scan.blocks_by_y.insert(-58, 500);
+        scan.blocks_by_y.insert(-57, 0);
+
+        (YHistogram::from_blocks("*diamond_ore", blocks.iter()), scan)
+    }
+
+    #[test]
+    fn rates_per_chunk_and_per_block() {
+        let (histogram, scan) = histogram();
+
+        assert_eq!(histogram.levels(), -59..=12);
+        assert_eq!(histogram.rate(-59, &scan, Normalization::Chunk), 2.0);
+        assert_eq!(histogram.rate(-58, &scan, Normalization::Chunk), 0.5);
+        assert_eq!(histogram.rate(-59, &scan, Normalization::Blocks), 2.0);
+        assert_eq!(histogram.rate(-58, &scan, Normalization::Blocks), 4.0);
+
+        // Levels without any blocks scanned, or without an entry at all, have no rate
+        assert_eq!(histogram.rate(-57, &scan, Normalization::Blocks), 0.0);
+        assert_eq!(histogram.rate(12, &scan, Normalization::Blocks), 0.0);
+
+        assert_eq!(
+            histogram.best_y(&scan, Normalization::Chunk),
+            Some((-59, 2.0))
+        );
+        assert_eq!(
+            histogram.best_y(&scan, Normalization::Blocks),
+            Some((-58, 4.0))
+        );
+
+        let empty = YHistogram::from_blocks("*emerald_ore", [].iter());
+        assert_eq!(empty.best_y(&scan, Normalization::Chunk), None);
+        assert_eq!(empty.levels().count(), 0);
+    }
+
+    #[test]
+    fn csv_and_json_output() {
+        let (mut histogram, scan) = histogram();
+        histogram.counts.remove(&12);
+
+        let write = |histogram: &YHistogram, format| {
+            let mut out = vec![];
+            write_histograms(
+                &mut out,
+                std::slice::from_ref(histogram),
+                &scan,
+                Normalization::Chunk,
+                format,
+            )
+            .unwrap();
+
+            String::from_utf8(out).unwrap()
+        };
+
+        assert_eq!(
+            write(&histogram, HistogramFormat::Csv),
+            "pattern,y,count,rate\n*diamond_ore,-59,8,2\n*diamond_ore,-58,2,0.5\n"
+        );
+        assert_eq!(
+            write(&histogram, HistogramFormat::Json),
+            concat!(
+                r#"[{"pattern":"*diamond_ore","normalization":"per chunk","best_y":-59,"#,
+                r#""levels":[{"y":-59,"count":8,"rate":2},{"y":-58,"count":2,"rate":0.5}]}]"#,
+                "\n"
+            )
+        );
+
+        // Patterns come from the command line, so they may hold anything
+        histogram.pattern = "say \"hi\"\\\u{1b}\n".to_string();
+        assert!(write(&histogram, HistogramFormat::Json)
+            .starts_with(r#"[{"pattern":"say \"hi\"\\\u001b\n","#));
+    }
+}
@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+
+use fastnbt::Value;
+use serde::Deserialize;
+use wildmatch::WildMatch;
+
+use super::blocks::BlockPatterns;
+
+/// An item stack, as stored before (`Count`, `tag`) or after (`count`, `components`) 1.20.5
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Item {
+    /// Empty slots are stored as empty compounds, so the id may be missing
+    #[serde(default)]
+    pub id: String,
+
+    #[serde(rename = "Count")]
+    old_count: Option<i32>,
+    count: Option<i32>,
+
+    tag: Option<ItemTag>,
+    components: Option<ItemComponents>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct ItemTag {
+    display: Option<Display>,
+
+    #[serde(rename = "Enchantments")]
+    enchantments: Option<Vec<Enchantment>>,
+
+    #[serde(rename = "StoredEnchantments")]
+    stored_enchantments: Option<Vec<Enchantment>>,
+
+    #[serde(rename = "BlockEntityTag")]
+    block_entity: Option<ItemContainer>,
+
+    /// Bundles before 1.20.5
+    #[serde(rename = "Items")]
+    items: Option<Vec<Item>>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct Display {
+    #[serde(rename = "Name")]
+    name: Option<Value>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct Enchantment {
+    id: Option<String>,
+    lvl: Option<i32>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct ItemContainer {
+    #[serde(rename = "Items")]
+    items: Option<Vec<Item>>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct ItemComponents {
+    #[serde(rename = "minecraft:custom_name")]
+    custom_name: Option<Value>,
+
+    #[serde(rename = "minecraft:enchantments")]
+    enchantments: Option<Value>,
+
+    #[serde(rename = "minecraft:stored_enchantments")]
+    stored_enchantments: Option<Value>,
+
+    /// Shulker boxes and other container blocks
+    #[serde(rename = "minecraft:container")]
+    container: Option<Vec<ContainerSlot>>,
+
+    #[serde(rename = "minecraft:bundle_contents")]
+    bundle_contents: Option<Vec<Item>>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct ContainerSlot {
+    item: Item,
+}
+
+impl Item {
+    pub fn is_empty(&self) -> bool {
+        self.id.is_empty() || self.id == "minecraft:air"
+    }
+
+    pub fn count(&self) -> i32 {
+        self.count.or(self.old_count).unwrap_or(1)
+    }
+
+    /// The name given to the item in an anvil, as plain text
+    pub fn custom_name(&self) -> Option<String> {
+        let name = match (&self.components, &self.tag) {
+            (
+                Some(ItemComponents {
+                    custom_name: Some(name),
+                    ..
+                }),
+                _,
+            ) => name,
+            (
+                _,
+                Some(ItemTag {
+                    display: Some(Display { name: Some(name) }),
+                    ..
+                }),
+            ) => name,
+            _ => return None,
+        };
+
+        Some(text_of(name))
+    }
+
+    /// Every enchantment on the item (or stored in an enchanted book) with its level
+    pub fn enchantments(&self) -> Vec<(String, i32)> {
+        let mut enchantments = vec![];
+
+        if let Some(tag) = &self.tag {
+            for list in [&tag.enchantments, &tag.stored_enchantments]
+                .into_iter()
+                .flatten()
+            {
+                enchantments.extend(list.iter().filter_map(|enchantment| {
+                    Some((enchantment.id.clone()?, enchantment.lvl.unwrap_or(1)))
+                }));
+            }
+        }
+
+        if let Some(components) = &self.components {
+            for value in [&components.enchantments, &components.stored_enchantments]
+                .into_iter()
+                .flatten()
+            {
+                enchantments.extend(enchantment_levels(value));
+            }
+        }
+
+        enchantments
+    }
+
+    /// The items stored inside this one, e.g. in a shulker box or bundle
+    pub fn contents(&self) -> Vec<&Item> {
+        let old = self.tag.iter().flat_map(|tag| {
+            let container = tag
+                .block_entity
+                .as_ref()
+                .and_then(|container| container.items.as_ref());
+
+            container.into_iter().chain(&tag.items).flatten()
+        });
+
+        let new = self.components.iter().flat_map(|components| {
+            let container = components.container.iter().flatten().map(|slot| &slot.item);
+
+            container.chain(components.bundle_contents.iter().flatten())
+        });
+
+        old.chain(new).filter(|item| !item.is_empty()).collect()
+    }
+}
+
+// Since 1.20.5 enchantments are a map of id to level, at first wrapped in `levels`
+fn enchantment_levels(value: &Value) -> Vec<(String, i32)> {
+    let levels = match value {
+        Value::Compound(map) => match map.get("levels") {
+            Some(Value::Compound(levels)) => levels,
+            _ => map,
+        },
+        _ => return vec![],
+    };
+
+    levels
+        .iter()
+        .filter_map(|(id, level)| Some((id.clone(), level.as_i64()? as i32)))
+        .collect()
+}
+
+/// The plain text of a text component, which may be a JSON string or NBT
+pub fn text_of(value: &Value) -> String {
+    match value {
+        Value::String(text) => match text.trim() {
+            json if json.starts_with('{') => json_text(json).unwrap_or_else(|| text.clone()),
+            json if json.starts_with('"') && json.ends_with('"') && json.len() >= 2 => {
+                json[1..json.len() - 1].to_string()
+            }
+            _ => text.clone(),
+        },
+        Value::Compound(map) => map.get("text").map(text_of).unwrap_or_default(),
+        Value::List(parts) => parts.iter().map(text_of).collect(),
+        _ => String::new(),
+    }
+}
+
+// The `text` of a JSON text component, without a JSON parser
+fn json_text(json: &str) -> Option<String> {
+    let start = json.find("\"text\"")? + "\"text\"".len();
+    let rest = json[start..].trim_start().strip_prefix(':')?.trim_start();
+    let rest = rest.strip_prefix('"')?;
+
+    Some(rest[..rest.find('"')?].to_string())
+}
+
+/// What to look for in inventories
+pub struct ItemQuery {
+    pub ids: BlockPatterns,
+
+    /// Matched against the custom name, ignoring case
+    pub name: Option<WildMatch>,
+
+    /// An enchantment id pattern and the lowest level that matches
+    pub enchantment: Option<(WildMatch, i32)>,
+}
+
+impl ItemQuery {
+    pub fn matches(&self, item: &Item) -> bool {
+        if !self.ids.matches(&item.id) {
+            return false;
+        }
+
+        let named = self.name.as_ref().is_none_or(|name| {
+            item.custom_name()
+                .is_some_and(|custom| name.matches(&custom.to_lowercase()))
+        });
+
+        let enchanted = self.enchantment.as_ref().is_none_or(|(id, min)| {
+            item.enchantments()
+                .iter()
+                .any(|(enchantment, level)| id.matches(enchantment) && level >= min)
+        });
+
+        named && enchanted
+    }
+}
+
+/// An item that matched a query along with the items it's nested in
+pub struct FoundItem<'a> {
+    pub item: &'a Item,
+
+    /// The items it's inside of, outermost first, e.g. a shulker box
+    pub within: Vec<&'a Item>,
+}
+
+/// Find the matching items in a list, including those nested inside other items
+pub fn find_items<'a>(items: &'a [Item], query: &ItemQuery) -> Vec<FoundItem<'a>> {
+    let mut found = vec![];
+
+    for item in items.iter().filter(|item| !item.is_empty()) {
+        find_nested(item, query, &mut vec![], &mut found);
+    }
+
+    found
+}
+
+fn find_nested<'a>(
+    item: &'a Item,
+    query: &ItemQuery,
+    within: &mut Vec<&'a Item>,
+    found: &mut Vec<FoundItem<'a>>,
+) {
+    if query.matches(item) {
+        found.push(FoundItem {
+            item,
+            within: within.clone(),
+        });
+    }
+
+    within.push(item);
+
+    for inner in item.contents() {
+        find_nested(inner, query, within, found);
+    }
+
+    within.pop();
+}
+
+/// Count the found items by id
+pub fn count_items<'a>(found: impl Iterator<Item = &'a FoundItem<'a>>) -> HashMap<String, i64> {
+    let mut counts = HashMap::new();
+
+    for found in found {
+        *counts.entry(found.item.id.clone()).or_default() += found.item.count() as i64;
+    }
+
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use fastnbt::nbt;
+
+    use super::*;
+    use crate::scanner::blocks::read_nbt;
+
+    #[test]
+    fn finds_items_nested_in_both_formats() {
+        let nbt = nbt!({"Items": [
+            {
+                "id": "minecraft:shulker_box",
+                "Count": 1i8,
+                "tag": {"BlockEntityTag": {"Items": [
+                    {
+                        "id": "minecraft:elytra",
+                        "Count": 1i8,
+                        "tag": {
+                            "display": {"Name": "{\"text\":\"Wings\"}"},
+                            "Enchantments": [{"id": "minecraft:mending", "lvl": 1i16}]
+                        }
+                    }
+                ]}}
+            },
+            {
+                "id": "minecraft:bundle",
+                "count": 1,
+                "components": {"minecraft:bundle_contents": [
+                    {
+                        "id": "minecraft:enchanted_book",
+                        "count": 1,
+                        "components": {
+                            "minecraft:custom_name": "\"Books\"",
+                            "minecraft:stored_enchantments": {"minecraft:mending": 1}
+                        }
+                    }
+                ]}
+            },
+            {
+                "id": "minecraft:bundle",
+                "Count": 1i8,
+                "tag": {"Items": [
+                    {
+                        "id": "minecraft:diamond_pickaxe",
+                        "Count": 1i8,
+                        "tag": {"Enchantments": [{"id": "minecraft:mending", "lvl": 1i16}]}
+                    }
+                ]}
+            },
+            {
+                "id": "minecraft:shulker_box",
+                "count": 1,
+                "components": {"minecraft:container": [
+                    {"slot": 0, "item": {
+                        "id": "minecraft:enchanted_book",
+                        "count": 1,
+                        "components": {"minecraft:stored_enchantments": {"minecraft:mending": 1}}
+                    }}
+                ]}
+            },
+            {}
+        ]});
+
+        // Read like a chunk's block entity would be, which widens the numbers
+        let container: ItemContainer = read_nbt(&nbt).unwrap();
+        let items = container.items.unwrap();
+
+        let query = ItemQuery {
+            ids: BlockPatterns::parse("*"),
+            name: None,
+            enchantment: Some((WildMatch::new("minecraft:mending"), 1)),
+        };
+
+        let found = find_items(&items, &query);
+        assert_eq!(found.len(), 4);
+        assert_eq!(found[0].item.custom_name().as_deref(), Some("Wings"));
+        assert_eq!(found[0].within[0].id, "minecraft:shulker_box");
+        assert_eq!(found[1].item.custom_name().as_deref(), Some("Books"));
+        assert_eq!(found[1].within[0].id, "minecraft:bundle");
+        assert_eq!(found[2].item.id, "minecraft:diamond_pickaxe");
+        assert_eq!(found[2].within[0].id, "minecraft:bundle");
+        assert_eq!(found[3].within[0].id, "minecraft:shulker_box");
+    }
+}
@@ -0,0 +1,312 @@
+use std::{fs::File, io, iter::repeat_n, path::Path};
+
+use fastanvil::{JavaChunk, Region, SectionLike};
+use fastnbt::{from_bytes, Value};
+use itertools::Itertools;
+
+use super::{
+    blocks::{BlockDescriptor, BlockEntity, BlockPatterns, ChunkEntityContainer},
+    bounds::Bounds,
+    census::AIR_BLOCKS,
+    scan::{scan_regions, ScanResult},
+    shapes::Shape,
+};
+
+/// Find every block matching the patterns inside the area of a dimension
+///
+/// Non-air blocks are only counted in sections that could contain a match.
+pub fn locate(path: &Path, area: &dyn Shape, patterns: &BlockPatterns) -> io::Result<ScanResult> {
+    locate_with(path, area, patterns, false)
+}
+
+/// Like `locate`, but count the non-air blocks of every section, e.g. for normalizing
+/// matches per block scanned
+pub fn locate_counting(
+    path: &Path,
+    area: &dyn Shape,
+    patterns: &BlockPatterns,
+) -> io::Result<ScanResult> {
+    locate_with(path, area, patterns, true)
+}
+
+fn locate_with(
+    path: &Path,
+    area: &dyn Shape,
+    patterns: &BlockPatterns,
+    count_all: bool,
+) -> io::Result<ScanResult> {
+    // Search for the specified block in each region
+    let (results, skipped_regions) = scan_regions(path, area, |file, rx, rz| {
+        locate_in_region(patterns, area, file, rx, rz, count_all)
+    })?;
+
+    // Summarize the results
+    let mut summary = ScanResult::combine(results.into_iter())
+        .filtering_blocks(|block| area.contains(&block.pos()))
+        .replacing_blocks(|blocks| blocks.into_iter().sorted());
+
+    summary.skipped_regions = skipped_regions;
+
+    Ok(summary)
+}
+
+pub fn locate_in_region(
+    patterns: &BlockPatterns,
+    boundary: &dyn Shape,
+    region: &mut Region<File>,
+    rx: i64,
+    rz: i64,
+    count_all: bool,
+) -> ScanResult {
+    let chunk_coords = (0..32i64).flat_map(|z| (0..32i64).map(move |x| (x, z)));
+
+    let mut result = ScanResult::new();
+    result.regions = 1;
+
+    for (cx, cz) in chunk_coords {
+        // Skip chunks outside of the search area before reading them
+        if !boundary.intersects(&Bounds::of_chunk(rx * 32 + cx, rz * 32 + cz)) {
+            result.skipped_chunks += 1;
+            continue;
+        }
+
+        let chunk = region.read_chunk(cx as usize, cz as usize);
+        let chunk = match chunk {
+            Ok(Some(data)) => Some(data),
+            _ => None,
+        };
+
+        let matching_entities = chunk
+            .clone()
+            .and_then(|data| from_bytes::<ChunkEntityContainer>(&data).ok())
+            .map_or(vec![], |container| container.block_entities)
+            .into_iter()
+            // Check the id before reading the rest of the fields
+            .filter(|nbt| match nbt {
+                Value::Compound(map) => {
+                    matches!(map.get("id"), Some(Value::String(id)) if patterns.matches(id))
+                }
+                _ => false,
+            })
+            .filter_map(BlockEntity::from_nbt)
+            .map(BlockDescriptor::from_entity)
+            .collect_vec();
+
+        result.found.extend(matching_entities);
+
+        let chunk = chunk.and_then(|data| match JavaChunk::from_bytes(&data) {
+            Ok(JavaChunk::Post18(chunk)) => Some(chunk),
+            _ => None,
+        });
+
+        let tower = chunk.and_then(|chunk| chunk.sections);
+        if tower.is_none() {
+            continue;
+        }
+
+        let mut has_non_air = false;
+
+        let tower = tower.unwrap();
+        for section in tower.sections() {
+            let palette = section.block_states.palette();
+            let is_air = palette
+                .iter()
+                .all(|block| AIR_BLOCKS.contains(&block.name()));
+            let sy = (section.y() as i64) * 16;
+            has_non_air = has_non_air || !is_air;
+
+            if !boundary.intersects(&Bounds::of_section(
+                rx * 32 + cx,
+                section.y() as i64,
+                rz * 32 + cz,
+            )) {
+                result.skipped_sections += 1;
+                continue;
+            }
+
+            // Skip air sections completely
+            if is_air {
+                continue;
+            }
+
+            let names = palette.iter().map(|block| block.name()).collect_vec();
+
+            locate_in_section(
+                &mut result,
+                patterns,
+                &names,
+                (rx * 512 + cx * 16, sy, rz * 512 + cz * 16),
+                || section.block_states.try_iter_indices(),
+                count_all,
+            );
+        }
+
+        if has_non_air {
+            result.chunks += 1;
+        }
+    }
+
+    result
+}
+
+/// Add the matches and non-air blocks of a section to the result
+///
+/// `indices` unpacks the palette index of every block, ordered by y, then z, then x,
+/// or gives `None` for sections of a single block.
+///
+/// Sections without a matching palette entry are only unpacked to count their
+/// blocks when `count_all` is set.
+fn locate_in_section<I: Iterator<Item = usize>>(
+    result: &mut ScanResult,
+    patterns: &BlockPatterns,
+    palette: &[&str],
+    (x0, sy, z0): (i64, i64, i64),
+    indices: impl FnOnce() -> Option<I>,
+    count_all: bool,
+) {
+    result.sections += 1;
+
+    // Test the pattern against each palette entry once rather than against every block
+    let matching = palette
+        .iter()
+        .map(|name| patterns.matches(name))
+        .collect_vec();
+    let airy = palette
+        .iter()
+        .map(|name| AIR_BLOCKS.contains(name))
+        .collect_vec();
+
+    let mut layers = [0usize; 16];
+
+    // Sections that can't contain a match only need their blocks counted, if at all
+    if !matching.contains(&true) {
+        result.unmatched_sections += 1;
+
+        if !count_all {
+            return;
+        }
+
+        match airy.contains(&true) {
+            true => {
+                for (i, block_index) in unpack(indices()).enumerate() {
+                    layers[i >> 8] += !airy[block_index] as usize;
+                }
+            }
+            false => layers = [256; 16],
+        }
+
+        result.count_blocks(sy, &layers);
+        return;
+    }
+
+    for (i, block_index) in unpack(indices()).enumerate() {
+        // Skip air blocks
+        if airy[block_index] {
+            continue;
+        }
+
+        layers[i >> 8] += 1;
+
+        if !matching[block_index] {
+            continue;
+        }
+
+        let x = (i & 0x000F) as i64;
+        let y = ((i & 0x0F00) >> 8) as i64;
+        let z = ((i & 0x00F0) >> 4) as i64;
+
+        result.found.push(BlockDescriptor::new(
+            palette[block_index],
+            x0 + x,
+            sy + y,
+            z0 + z,
+        ));
+    }
+
+    result.count_blocks(sy, &layers);
+}
+
+// Sections of a single block don't store any indices
+fn unpack<'a, I>(indices: Option<I>) -> Box<dyn Iterator<Item = usize> + 'a>
+where
+    I: Iterator<Item = usize> + 'a,
+{
+    match indices {
+        Some(iter) => Box::new(iter),
+        None => Box::new(repeat_n(0, 4096)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unmatched_sections_are_not_unpacked() {
+        let patterns = BlockPatterns::parse("*diamond_ore");
+        let palette = ["minecraft:air", "minecraft:stone"];
+        let mut result = ScanResult::new();
+
+        locate_in_section(
+            &mut result,
+            &patterns,
+            &palette,
+            (0, 0, 0),
+            || -> Option<std::iter::Empty<usize>> { panic!("unpacked an unmatched section") },
+            false,
+        );
+        assert_eq!((result.sections, result.unmatched_sections), (1, 1));
+        assert_eq!(result.blocks, 0);
+
+        // Counting every block unpacks them after all: one layer of stone
+        let layer = || Some((0..4096).map(|i| (i < 256) as usize));
+        locate_in_section(&mut result, &patterns, &palette, (0, 0, 0), layer, true);
+        assert_eq!(result.blocks, 256);
+        assert_eq!(result.blocks_by_y[&0], 256);
+
+        let palette = ["minecraft:stone", "minecraft:diamond_ore"];
+        let ore = || Some((0..4096).map(|i| (i == 17) as usize));
+        locate_in_section(&mut result, &patterns, &palette, (16, -64, 0), ore, false);
+        assert_eq!(
+            result.found,
+            vec![BlockDescriptor::new("minecraft:diamond_ore", 17, -64, 1)]
+        );
+        assert_eq!(result.blocks, 256 + 4096);
+    }
+
+    #[test]
+    fn cave_air_and_uniform_sections() {
+        let patterns = BlockPatterns::parse("*diamond_ore");
+        let none = || -> Option<std::iter::Empty<usize>> { None };
+        let mut result = ScanResult::new();
+
+        // Solid sections of a single block count all of their blocks
+        locate_in_section(
+            &mut result,
+            &patterns,
+            &["minecraft:deepslate"],
+            (0, -64, 0),
+            none,
+            true,
+        );
+        assert_eq!(result.blocks, 4096);
+        assert_eq!(result.blocks_by_y[&-64], 256);
+
+        // Cave air isn't counted as a block
+        let half = || Some((0..4096).map(|i| (i < 2048) as usize));
+        let palette = ["minecraft:stone", "minecraft:cave_air"];
+        locate_in_section(&mut result, &patterns, &palette, (0, 0, 0), half, true);
+        assert_eq!(result.blocks, 4096 + 2048);
+
+        // And a section made of nothing but the block matches everywhere
+        locate_in_section(
+            &mut result,
+            &patterns,
+            &["minecraft:diamond_ore"],
+            (0, 16, 0),
+            none,
+            false,
+        );
+        assert_eq!(result.found.len(), 4096);
+    }
+}
@@ -0,0 +1,277 @@
+use std::{collections::HashMap, fs::File, path::Path};
+
+use fastanvil::{JavaChunk, RCoord, Region, RegionFileLoader, RegionLoader};
+use fastnbt::{from_bytes, ByteArray};
+use serde::Deserialize;
+
+use super::points::Point;
+
+// The blocks of one section: its palette and, unless it's a single block type, an index per block
+struct SectionBlocks {
+    palette: Vec<String>,
+    indices: Option<Vec<usize>>,
+    block_light: Option<ByteArray>,
+    sky_light: Option<ByteArray>,
+
+    /// Whether the game has calculated the light of the section's chunk
+    lit: bool,
+}
+
+// Light levels aren't part of fastanvil's sections, so they're read separately
+#[derive(Deserialize)]
+struct ChunkLight {
+    #[serde(default)]
+    sections: Vec<SectionLight>,
+
+    #[serde(rename = "isLightOn")]
+    light_on: Option<i8>,
+}
+
+#[derive(Deserialize)]
+struct SectionLight {
+    #[serde(rename = "Y")]
+    y: i8,
+
+    #[serde(rename = "BlockLight")]
+    block_light: Option<ByteArray>,
+
+    #[serde(rename = "SkyLight")]
+    sky_light: Option<ByteArray>,
+}
+
+/// The light at a position, from torches and the like (block) and from the sky
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Light {
+    pub block: u8,
+
+    /// `None` in dimensions without sky light, i.e. the Nether and the End
+    pub sky: Option<u8>,
+}
+
+/// Reads chunks of a dimension by their chunk coordinates, keeping region files open
+pub struct ChunkReader {
+    loader: RegionFileLoader,
+    regions: HashMap<(i64, i64), Option<Region<File>>>,
+}
+
+impl ChunkReader {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            loader: RegionFileLoader::new(path.join("region")),
+            regions: HashMap::new(),
+        }
+    }
+
+    /// The raw NBT of a chunk, or `None` if it hasn't been generated
+    pub fn read(&mut self, cx: i64, cz: i64) -> Option<Vec<u8>> {
+        let (rx, rz) = (cx.div_euclid(32), cz.div_euclid(32));
+
+        let loader = &self.loader;
+        let region = self
+            .regions
+            .entry((rx, rz))
+            .or_insert_with(|| {
+                loader
+                    .region(RCoord(rx as isize), RCoord(rz as isize))
+                    .ok()
+                    .flatten()
+            })
+            .as_mut()?;
+
+        region
+            .read_chunk(cx.rem_euclid(32) as usize, cz.rem_euclid(32) as usize)
+            .ok()
+            .flatten()
+    }
+}
+
+/// Random access to the blocks of a dimension
+///
+/// Regions and sections are read on demand and cached, so looking up the
+/// neighbors of blocks that are close together only reads each section once.
+pub struct BlockLookup {
+    chunks: ChunkReader,
+    sections: HashMap<(i64, i64, i64), Option<SectionBlocks>>,
+    has_sky_light: bool,
+}
+
+impl BlockLookup {
+    pub fn new(path: &Path) -> Self {
+        let has_sky_light = !matches!(
+            path.file_name().and_then(|name| name.to_str()),
+            Some("DIM-1" | "DIM1" | "the_nether" | "the_end")
+        );
+
+        Self {
+            chunks: ChunkReader::new(path),
+            sections: HashMap::new(),
+            has_sky_light,
+        }
+    }
+
+    /// The name of the block at a position, or `None` if it hasn't been generated
+    pub fn block_at(&mut self, point: &Point) -> Option<&str> {
+        let section = self.section(point)?;
+        let index = match &section.indices {
+            Some(indices) => indices[index_in_section(point)],
+            None => 0,
+        };
+
+        section.palette.get(index).map(String::as_str)
+    }
+
+    /// The light level at a position, or `None` if the chunk's light hasn't been calculated
+    pub fn light_at(&mut self, point: &Point) -> Option<Light> {
+        let has_sky_light = self.has_sky_light;
+        let section = self.section(point)?;
+        let i = index_in_section(point);
+
+        if !section.lit {
+            return None;
+        }
+
+        // Two 4 bit levels per byte, the first one in the low bits
+        let nibble = |levels: &ByteArray| {
+            let byte = *levels.get(i >> 1)? as u8;
+
+            Some(match i & 1 {
+                0 => byte & 0x0F,
+                _ => byte >> 4,
+            })
+        };
+
+        // The game leaves block light out of sections without any
+        let block = match &section.block_light {
+            Some(levels) => nibble(levels)?,
+            None => 0,
+        };
+
+        let sky = match has_sky_light {
+            true => Some(nibble(section.sky_light.as_ref()?)?),
+            false => None,
+        };
+
+        Some(Light { block, sky })
+    }
+
+    fn section(&mut self, point: &Point) -> Option<&SectionBlocks> {
+        let key = (
+            point.x.div_euclid(16),
+            point.y.div_euclid(16),
+            point.z.div_euclid(16),
+        );
+
+        if !self.sections.contains_key(&key) {
+            let (cx, sy, cz) = key;
+
+            // Reading a chunk loads all of its sections at once
+            for (y, section) in self.read_chunk(cx, cz).unwrap_or_default() {
+                self.sections.insert((cx, y, cz), Some(section));
+            }
+
+            self.sections.entry((cx, sy, cz)).or_insert(None);
+        }
+
+        self.sections.get(&key)?.as_ref()
+    }
+
+    fn read_chunk(&mut self, cx: i64, cz: i64) -> Option<Vec<(i64, SectionBlocks)>> {
+        let data = self.chunks.read(cx, cz)?;
+
+        let tower = match JavaChunk::from_bytes(&data) {
+            Ok(JavaChunk::Post18(chunk)) => chunk.sections?,
+            _ => return None,
+        };
+
+        let (mut light, lit) = match from_bytes::<ChunkLight>(&data) {
+            Ok(chunk) => (chunk.sections, chunk.light_on != Some(0)),
+            Err(_) => (vec![], false),
+        };
+
+        let sections = tower
+            .sections()
+            .iter()
+            .map(|section| {
+                let light = light
+                    .iter()
+                    .position(|light| light.y == section.y)
+                    .map(|index| light.swap_remove(index));
+
+                let (block_light, sky_light) = match light {
+                    Some(light) => (light.block_light, light.sky_light),
+                    None => (None, None),
+                };
+
+                let blocks = SectionBlocks {
+                    palette: section
+                        .block_states
+                        .palette()
+                        .iter()
+                        .map(|block| block.name().to_string())
+                        .collect(),
+                    indices: section
+                        .block_states
+                        .try_iter_indices()
+                        .map(|iter| iter.collect()),
+                    block_light,
+                    sky_light,
+                    lit,
+                };
+
+                (section.y as i64, blocks)
+            })
+            .collect();
+
+        Some(sections)
+    }
+}
+
+#[cfg(test)]
+impl BlockLookup {
+    /// Replace a section with blocks and block light built from their positions
+    pub fn set_section(
+        &mut self,
+        (cx, sy, cz): (i64, i64, i64),
+        block: impl Fn(&Point) -> (&'static str, u8),
+    ) {
+        let mut palette = vec![];
+        let mut indices = vec![];
+        let mut block_light = vec![0i8; 2048];
+
+        for i in 0..4096 {
+            let point = Point {
+                x: cx * 16 + (i & 15) as i64,
+                y: sy * 16 + (i >> 8) as i64,
+                z: cz * 16 + (i >> 4 & 15) as i64,
+            };
+            let (name, light) = block(&point);
+
+            let index = match palette.iter().position(|known| known == name) {
+                Some(index) => index,
+                None => {
+                    palette.push(name.to_string());
+                    palette.len() - 1
+                }
+            };
+
+            indices.push(index);
+            block_light[i >> 1] |= ((light & 0x0F) << (4 * (i & 1))) as i8;
+        }
+
+        let section = SectionBlocks {
+            palette,
+            indices: Some(indices),
+            block_light: Some(ByteArray::new(block_light)),
+            // Full sky light everywhere, where the dimension has any
+            sky_light: self.has_sky_light.then(|| ByteArray::new(vec![-1; 2048])),
+            lit: true,
+        };
+
+        self.sections.insert((cx, sy, cz), Some(section));
+    }
+}
+
+// The index of a position in a section's blocks, ordered by y, then z, then x
+fn index_in_section(point: &Point) -> usize {
+    (point.y.rem_euclid(16) << 8 | point.z.rem_euclid(16) << 4 | point.x.rem_euclid(16)) as usize
+}
@@ -0,0 +1,104 @@
+use std::{io, path::Path};
+
+use fastnbt::Value;
+use serde::Deserialize;
+use wildmatch::WildMatch;
+
+use super::{
+    blocks::{read_nbt, BlockPatterns},
+    entities::{position_of, scan_entities},
+    locate::locate,
+    points::Point,
+    shapes::Shape,
+};
+
+/// The blocks that can hold loot until they're first opened or brushed
+const LOOT_BLOCKS: &str = "*chest,*barrel,*dispenser,*dropper,*hopper,*shulker_box,\
+    *suspicious_sand,*suspicious_gravel,*decorated_pot";
+
+/// Minecarts with chests hold most of the loot in mineshafts
+const LOOT_ENTITIES: &str = "minecraft:chest_minecart,minecraft:hopper_minecart";
+
+/// A block or minecart that fills itself from a loot table the first time it's opened
+pub struct LootContainer {
+    pub id: String,
+    pub position: Point,
+    pub table: String,
+    pub seed: Option<i64>,
+
+    /// Everything the block entity or minecart stores
+    pub nbt: Value,
+}
+
+#[derive(Deserialize, Debug)]
+struct LootEntity {
+    id: String,
+
+    #[serde(rename = "Pos")]
+    pos: Vec<f64>,
+
+    #[serde(rename = "LootTable")]
+    loot_table: Option<String>,
+
+    #[serde(rename = "LootTableSeed")]
+    loot_table_seed: Option<i64>,
+}
+
+/// Every container in the area of a dimension that nobody has opened yet
+pub fn find_loot(path: &Path, area: &dyn Shape) -> io::Result<Vec<LootContainer>> {
+    let blocks = locate(path, area, &BlockPatterns::parse(LOOT_BLOCKS))?
+        .found
+        .into_iter()
+        .filter_map(|block| block.enity)
+        .filter_map(|entity| {
+            Some(LootContainer {
+                position: entity.pos(),
+                table: entity.loot_table?,
+                seed: entity.loot_table_seed,
+                id: entity.id,
+                nbt: entity.nbt,
+            })
+        });
+
+    let minecarts = BlockPatterns::parse(LOOT_ENTITIES);
+    let entities = scan_entities(path, area, |nbt: Value| {
+        let entity = read_nbt::<LootEntity>(&nbt)?;
+        let position = position_of(&entity.pos)?;
+
+        (minecarts.matches(&entity.id) && area.contains(&position)).then_some(LootContainer {
+            id: entity.id,
+            position,
+            table: entity.loot_table?,
+            seed: entity.loot_table_seed,
+            nbt,
+        })
+    })?;
+
+    Ok(blocks.chain(entities).collect())
+}
+
+/// Whether a loot table matches a pattern, which may leave out the table's namespace
+pub fn matches_table(pattern: &WildMatch, table: &str) -> bool {
+    let name = table.split_once(':').map_or(table, |(_, name)| name);
+
+    pattern.matches(table) || pattern.matches(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tables_match_with_or_without_a_namespace() {
+        let table = "minecraft:chests/abandoned_mineshaft";
+
+        assert!(matches_table(&WildMatch::new("chests/abandoned_*"), table));
+        assert!(matches_table(
+            &WildMatch::new("minecraft:chests/abandoned_mineshaft"),
+            table
+        ));
+        assert!(matches_table(&WildMatch::new("*mineshaft"), table));
+        assert!(!matches_table(&WildMatch::new("chests/end_city*"), table));
+        assert!(!matches_table(&WildMatch::new("mymod:chests/*"), table));
+    }
+}
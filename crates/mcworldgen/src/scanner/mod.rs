@@ -1,5 +1,31 @@
+pub mod afk;
 pub mod blocks;
 pub mod bounds;
+pub mod branch;
+pub mod census;
+pub mod containers;
+pub mod deposits;
+pub mod distance;
+pub mod entities;
+pub mod exposure;
+pub mod fluids;
+pub mod groups;
+pub mod histogram;
+pub mod items;
+pub mod locate;
+pub mod lookup;
+pub mod loot;
+pub mod nbt_path;
 pub mod points;
+pub mod portals;
+pub mod route;
+pub mod saved;
 pub mod scan;
+pub mod shapes;
+pub mod stats;
+pub mod structures;
+pub mod template;
+pub mod vein_stats;
 pub mod veins;
+pub mod viability;
+pub mod voxels;
@@ -0,0 +1,245 @@
+use std::{fmt::Display, str::FromStr};
+
+use fastnbt::Value;
+use wildmatch::WildMatch;
+
+/// A condition on a value inside NBT data, e.g. `Items[].id=minecraft:diamond`
+///
+/// The path names compound keys separated by dots. `[]` steps into every element
+/// of a list and `[n]` into one. The predicate holds when any value the path leads
+/// to satisfies the comparison; a path alone only has to lead somewhere.
+#[derive(Debug, Clone)]
+pub struct NbtPredicate {
+    path: Vec<Step>,
+    comparison: Comparison,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Key(String),
+    Each,
+    Index(usize),
+}
+
+#[derive(Debug, Clone)]
+enum Comparison {
+    Exists,
+
+    /// Wildcards are allowed when comparing text
+    Equals(String),
+    NotEquals(String),
+
+    /// Text containing a value, ignoring case
+    Contains(String),
+    Less(f64),
+    LessOrEqual(f64),
+    Greater(f64),
+    GreaterOrEqual(f64),
+}
+
+#[derive(Debug)]
+pub struct NbtPathError(String);
+
+impl Display for NbtPathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// Longer operators first so `!=` isn't read as `=`
+const OPERATORS: [&str; 7] = ["!=", "<=", ">=", "=", "~", "<", ">"];
+
+impl FromStr for NbtPredicate {
+    type Err = NbtPathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let operator = s.char_indices().find_map(|(i, _)| {
+            OPERATORS
+                .iter()
+                .find(|op| s[i..].starts_with(*op))
+                .map(|op| (i, *op))
+        });
+
+        let (path, comparison) = match operator {
+            None => (s, Comparison::Exists),
+            Some((i, op)) => {
+                let value = unquote(s[i + op.len()..].trim());
+                let number = || {
+                    value
+                        .parse::<f64>()
+                        .map_err(|_| NbtPathError(format!("'{}' needs a number after '{}'", s, op)))
+                };
+
+                let comparison = match op {
+                    "=" => Comparison::Equals(value.to_string()),
+                    "!=" => Comparison::NotEquals(value.to_string()),
+                    "~" => Comparison::Contains(value.to_lowercase()),
+                    "<" => Comparison::Less(number()?),
+                    "<=" => Comparison::LessOrEqual(number()?),
+                    ">" => Comparison::Greater(number()?),
+                    _ => Comparison::GreaterOrEqual(number()?),
+                };
+
+                (&s[..i], comparison)
+            }
+        };
+
+        Ok(Self {
+            path: parse_path(path.trim())
+                .ok_or_else(|| NbtPathError(format!("Invalid NBT path '{}'", path.trim())))?,
+            comparison,
+        })
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|value| value.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+
+    value
+}
+
+fn parse_path(path: &str) -> Option<Vec<Step>> {
+    let mut steps = vec![];
+
+    for part in path.split('.') {
+        let (key, mut indices) = match part.find('[') {
+            Some(i) => (&part[..i], &part[i..]),
+            None => (part, ""),
+        };
+
+        if key.is_empty() {
+            return None;
+        }
+
+        steps.push(Step::Key(key.to_string()));
+
+        while let Some(rest) = indices.strip_prefix('[') {
+            let end = rest.find(']')?;
+
+            steps.push(match &rest[..end] {
+                "" => Step::Each,
+                index => Step::Index(index.parse().ok()?),
+            });
+
+            indices = &rest[end + 1..];
+        }
+
+        if !indices.is_empty() {
+            return None;
+        }
+    }
+
+    Some(steps)
+}
+
+impl NbtPredicate {
+    pub fn matches(&self, nbt: &Value) -> bool {
+        let mut values = vec![nbt];
+
+        for step in &self.path {
+            values = values
+                .into_iter()
+                .flat_map(|value| match (step, value) {
+                    (Step::Key(key), Value::Compound(map)) => map.get(key).into_iter().collect(),
+                    (Step::Each, Value::List(list)) => list.iter().collect(),
+                    (Step::Index(index), Value::List(list)) => {
+                        list.get(*index).into_iter().collect()
+                    }
+                    _ => vec![],
+                })
+                .collect();
+        }
+
+        values.into_iter().any(|value| self.comparison.holds(value))
+    }
+}
+
+impl Comparison {
+    fn holds(&self, value: &Value) -> bool {
+        let number = number_of(value);
+        let text = match value {
+            Value::String(text) => Some(text.as_str()),
+            _ => None,
+        };
+
+        match self {
+            Comparison::Exists => true,
+            Comparison::Equals(expected) => equals(number, text, expected),
+            Comparison::NotEquals(expected) => {
+                (number.is_some() || text.is_some()) && !equals(number, text, expected)
+            }
+            Comparison::Contains(part) => {
+                text.is_some_and(|text| text.to_lowercase().contains(part))
+            }
+            Comparison::Less(limit) => number.is_some_and(|n| n < *limit),
+            Comparison::LessOrEqual(limit) => number.is_some_and(|n| n <= *limit),
+            Comparison::Greater(limit) => number.is_some_and(|n| n > *limit),
+            Comparison::GreaterOrEqual(limit) => number.is_some_and(|n| n >= *limit),
+        }
+    }
+}
+
+fn equals(number: Option<f64>, text: Option<&str>, expected: &str) -> bool {
+    match (number, text) {
+        (Some(number), _) => expected
+            .parse::<f64>()
+            .is_ok_and(|expected| number == expected),
+        (_, Some(text)) => WildMatch::new(expected).matches(text),
+        _ => false,
+    }
+}
+
+fn number_of(value: &Value) -> Option<f64> {
+    match value {
+        Value::Byte(n) => Some(*n as f64),
+        Value::Short(n) => Some(*n as f64),
+        Value::Int(n) => Some(*n as f64),
+        Value::Long(n) => Some(*n as f64),
+        Value::Float(n) => Some(*n as f64),
+        Value::Double(n) => Some(*n),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fastnbt::nbt;
+
+    use super::*;
+
+    fn matches(predicate: &str, nbt: &Value) -> bool {
+        predicate.parse::<NbtPredicate>().unwrap().matches(nbt)
+    }
+
+    #[test]
+    fn predicates() {
+        let chest = nbt!({
+            "CustomName": "{\"text\":\"Base Storage\"}",
+            "Lock": "",
+            "Items": [
+                {"id": "minecraft:diamond", "Count": 12i8},
+                {"id": "minecraft:elytra", "Count": 1i8}
+            ]
+        });
+
+        assert!(matches("Items[].id=minecraft:diamond", &chest));
+        assert!(matches("Items[1].id=*elytra", &chest));
+        assert!(!matches("Items[0].id=*elytra", &chest));
+        assert!(matches("Items[].Count>=12", &chest));
+        assert!(!matches("Items[].Count>12", &chest));
+        assert!(matches("CustomName~\"base\"", &chest));
+        assert!(matches("Lock", &chest));
+        assert!(!matches("Lock!=", &chest));
+        assert!(!matches("LootTable", &chest));
+
+        assert!("Items[x].id=a".parse::<NbtPredicate>().is_err());
+        assert!("Items[].Count>many".parse::<NbtPredicate>().is_err());
+    }
+}
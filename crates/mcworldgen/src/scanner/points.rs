@@ -1,3 +1,7 @@
+use std::str::FromStr;
+
+use itertools::Itertools;
+
 #[derive(Clone, Eq, PartialEq, Debug, Hash)]
 pub struct Point {
     pub x: i64,
@@ -5,13 +9,44 @@ pub struct Point {
     pub z: i64,
 }
 
-pub enum Face {
-    Top,
-    Bottom,
-    Left,
-    Right,
-    Front,
-    Back,
+/// Which surrounding blocks count as touching when grouping blocks into veins
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Connectivity {
+    /// The 6 blocks sharing a face
+    Faces,
+
+    /// The 18 blocks sharing a face or an edge
+    Edges,
+
+    /// The 26 blocks sharing a face, an edge or a corner
+    Corners,
+}
+
+impl Connectivity {
+    // How many axes an offset may move along
+    fn axes(&self) -> usize {
+        match self {
+            Connectivity::Faces => 1,
+            Connectivity::Edges => 2,
+            Connectivity::Corners => 3,
+        }
+    }
+}
+
+impl FromStr for Connectivity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "6" => Ok(Connectivity::Faces),
+            "18" => Ok(Connectivity::Edges),
+            "26" => Ok(Connectivity::Corners),
+            _ => Err(format!(
+                "Unknown connectivity '{}', expected 6, 18 or 26",
+                s
+            )),
+        }
+    }
 }
 
 impl From<i64> for Point {
@@ -27,69 +62,27 @@ impl From<i64> for Point {
 impl Point {
     pub const ZERO: Self = Self { x: 0, y: 0, z: 0 };
 
-    pub fn distance_to(&self, other: &Point) -> i64 {
-        let dx = self.x - other.x;
-        let dy = self.y - other.y;
-        let dz = self.z - other.z;
-
-        let dist_squared = dx * dx + dy * dy + dz * dz;
-
-        (dist_squared as f64).sqrt().trunc() as i64
-    }
-
-    pub fn is_near(&self, point: &Point, threshold: &Point) -> bool {
-        point.x - self.x <= threshold.x
-            && point.y - self.y <= threshold.y
-            && point.z - self.z <= threshold.z
-    }
-
-    pub fn move_toward(&self, face: Face) -> Self {
-        let delta = match face {
-            Face::Top => (0, 1, 0),
-            Face::Bottom => (0, -1, 0),
-            Face::Left => (-1, 0, 0),
-            Face::Right => (1, 0, 0),
-            Face::Front => (0, 0, 1),
-            Face::Back => (0, 0, -1),
-        };
-
-        Self {
-            x: self.x + delta.0,
-            y: self.y + delta.1,
-            z: self.z + delta.2,
-        }
-    }
+    /// The positions connected to this one, up to `reach` blocks away along each axis
+    ///
+    /// A reach of 1 gives the direct neighbors (6, 18 or 26 of them). Larger
+    /// reaches also connect blocks separated by a gap of `reach - 1` blocks.
+    pub fn neighbors(&self, connectivity: Connectivity, reach: i64) -> impl Iterator<Item = Point> {
+        let (x, y, z) = (self.x, self.y, self.z);
+        let offsets = -reach..=reach;
 
-    // The neighboring positions that would directly touch one of this blocks faces
-    pub fn neighbors(&self) -> impl Iterator<Item = Point> {
-        vec![
-            self.move_toward(Face::Top),
-            // self.move_toward(Face::Top).move_toward(Face::Left),
-            // self.move_toward(Face::Top).move_toward(Face::Left).move_toward(Face::Front),
-            // self.move_toward(Face::Top).move_toward(Face::Left).move_toward(Face::Back),
-            // self.move_toward(Face::Top).move_toward(Face::Right),
-            // self.move_toward(Face::Top).move_toward(Face::Right).move_toward(Face::Front),
-            // self.move_toward(Face::Top).move_toward(Face::Right).move_toward(Face::Back),
-            // self.move_toward(Face::Top).move_toward(Face::Front),
-            // self.move_toward(Face::Top).move_toward(Face::Back),
-            self.move_toward(Face::Bottom),
-            // self.move_toward(Face::Bottom).move_toward(Face::Left),
-            // self.move_toward(Face::Bottom).move_toward(Face::Left).move_toward(Face::Front),
-            // self.move_toward(Face::Bottom).move_toward(Face::Left).move_toward(Face::Back),
-            // self.move_toward(Face::Bottom).move_toward(Face::Right),
-            // self.move_toward(Face::Bottom).move_toward(Face::Right).move_toward(Face::Front),
-            // self.move_toward(Face::Bottom).move_toward(Face::Right).move_toward(Face::Back),
-            // self.move_toward(Face::Bottom).move_toward(Face::Front),
-            // self.move_toward(Face::Bottom).move_toward(Face::Back),
-            self.move_toward(Face::Left),
-            // self.move_toward(Face::Left).move_toward(Face::Front),
-            // self.move_toward(Face::Left).move_toward(Face::Back),
-            self.move_toward(Face::Right),
-            // self.move_toward(Face::Right).move_toward(Face::Front),
-            // self.move_toward(Face::Right).move_toward(Face::Back),
-            self.move_toward(Face::Front),
-            self.move_toward(Face::Back),
-        ]
-        .into_iter()
+        offsets
+            .clone()
+            .cartesian_product(offsets.clone())
+            .cartesian_product(offsets)
+            .map(|((dx, dy), dz)| (dx, dy, dz))
+            .filter(move |(dx, dy, dz)| {
+                let axes = [dx, dy, dz].iter().filter(|d| ***d != 0).count();
+                axes > 0 && axes <= connectivity.axes()
+            })
+            .map(move |(dx, dy, dz)| Point {
+                x: x + dx,
+                y: y + dy,
+                z: z + dz,
+            })
     }
 }
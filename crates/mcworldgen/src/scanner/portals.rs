@@ -0,0 +1,283 @@
+use std::{collections::HashMap, io, path::Path};
+
+use fastnbt::IntArray;
+use itertools::Itertools;
+use serde::Deserialize;
+
+use super::{
+    blocks::{BlockDescriptor, BlockPatterns},
+    bounds::Bounds,
+    distance::Scale,
+    locate::locate,
+    points::Point,
+    scan::{read_chunks, scan_region_files},
+    shapes::Shape,
+    veins::{find_veins, Linking},
+};
+
+const PORTAL: &str = "minecraft:nether_portal";
+
+// Points of interest are kept per section, keyed by the section's Y as a string
+#[derive(Deserialize, Debug)]
+struct PoiChunk {
+    #[serde(rename = "Sections", default)]
+    sections: HashMap<String, PoiSection>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PoiSection {
+    #[serde(rename = "Records", default)]
+    records: Vec<PoiRecord>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PoiRecord {
+    pos: IntArray,
+
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+/// The nether portal blocks in the area of a dimension
+///
+/// The game keeps every portal block as a point of interest, which is much faster
+/// to read than the blocks themselves. Dimensions without POI data are scanned.
+pub fn find_portal_blocks(path: &Path, area: &dyn Shape) -> io::Result<Vec<Point>> {
+    let poi = path.join("poi");
+
+    let blocks = match poi.is_dir() {
+        true => {
+            let (found, _) = scan_region_files(&poi, area, |region, rx, rz| {
+                read_chunks(region, rx, rz, area, |chunk: PoiChunk| {
+                    chunk
+                        .sections
+                        .into_values()
+                        .flat_map(|section| section.records)
+                        .filter(|record| record.kind == PORTAL)
+                        .filter_map(|record| match *record.pos {
+                            [x, y, z] => Some(Point {
+                                x: x as i64,
+                                y: y as i64,
+                                z: z as i64,
+                            }),
+                            _ => None,
+                        })
+                        .collect()
+                })
+            })?;
+
+            found.into_iter().flatten().collect_vec()
+        }
+        false => locate(path, area, &BlockPatterns::parse(PORTAL))?
+            .found
+            .iter()
+            .map(BlockDescriptor::pos)
+            .collect_vec(),
+    };
+
+    Ok(blocks
+        .into_iter()
+        .filter(|point| area.contains(point))
+        .collect())
+}
+
+/// One portal: a connected group of portal blocks
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Portal {
+    pub bounds: Bounds,
+    pub blocks: Vec<Point>,
+}
+
+impl Portal {
+    /// Where a player walking into the portal stands: the bottom of its middle
+    pub fn entry(&self) -> Point {
+        Point {
+            y: self.bounds.min.y,
+            ..self.bounds.center()
+        }
+    }
+}
+
+/// Group portal blocks into portals, ordered by position
+pub fn group_portals(blocks: Vec<Point>) -> Vec<Portal> {
+    let blocks = blocks
+        .into_iter()
+        .map(|point| BlockDescriptor::new(PORTAL, point.x, point.y, point.z))
+        .collect_vec();
+
+    find_veins(blocks, Linking::default())
+        .map(|vein| Portal {
+            bounds: vein.bounds,
+            blocks: vein.blocks.iter().map(BlockDescriptor::pos).collect(),
+        })
+        .sorted_by_key(|portal| {
+            (
+                portal.bounds.min.x,
+                portal.bounds.min.z,
+                portal.bounds.min.y,
+            )
+        })
+        .collect()
+}
+
+/// How far from the scaled position the game looks for a portal to link to
+pub fn search_radius(to: Scale) -> i64 {
+    match to {
+        Scale::Nether => 16,
+        Scale::Overworld => 128,
+    }
+}
+
+/// How far past the search area a dimension has to be scanned to find every portal
+/// that the area's portals link to, and every portal those lead back to
+///
+/// A portal linked to from the other dimension can sit that dimension's search radius
+/// (scaled) past the area, and its own search reaches this dimension's radius further.
+pub fn scan_margin(dimension: Scale) -> i64 {
+    let other = match dimension {
+        Scale::Overworld => Scale::Nether,
+        Scale::Nether => Scale::Overworld,
+    };
+
+    let linked = Point {
+        x: search_radius(other),
+        y: 0,
+        z: 0,
+    }
+    .rescale(other, dimension);
+
+    linked.x + search_radius(dimension)
+}
+
+/// Where going through a portal leads
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Link {
+    /// The entry scaled into the other dimension, where the search is centered
+    pub target: Point,
+
+    /// The index of the portal that is linked to and the block the player arrives at,
+    /// or `None` when a new portal would be generated
+    pub destination: Option<(usize, Point)>,
+}
+
+/// Find the portal a player entering at `entry` arrives at in the other dimension
+///
+/// Like the game this picks the closest portal block in a square of `search_radius`
+/// around the scaled position, spanning every Y level, preferring lower blocks on ties.
+pub fn find_link(entry: &Point, from: Scale, to: Scale, portals: &[Portal]) -> Link {
+    let target = entry.rescale(from, to);
+    let radius = search_radius(to);
+
+    let destination = portals
+        .iter()
+        .enumerate()
+        .flat_map(|(index, portal)| portal.blocks.iter().map(move |block| (index, block)))
+        .filter(|(_, block)| {
+            (block.x - target.x).abs() <= radius && (block.z - target.z).abs() <= radius
+        })
+        .min_by_key(|(_, block)| {
+            let (dx, dy, dz) = (block.x - target.x, block.y - target.y, block.z - target.z);
+            (dx * dx + dy * dy + dz * dz, block.y)
+        })
+        .map(|(index, block)| (index, block.clone()));
+
+    Link {
+        target,
+        destination,
+    }
+}
+
+/// Where every portal of one dimension leads in the other
+pub fn link_portals(
+    from: &[Portal],
+    from_scale: Scale,
+    to: &[Portal],
+    to_scale: Scale,
+) -> Vec<Link> {
+    from.iter()
+        .map(|portal| find_link(&portal.entry(), from_scale, to_scale, to))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn portal(x: i64, y: i64, z: i64) -> Vec<Point> {
+        (x..x + 2)
+            .flat_map(|x| (y..y + 3).map(move |y| Point { x, y, z }))
+            .collect()
+    }
+
+    #[test]
+    fn two_portals_share_one_exit() {
+        let overworld = group_portals([portal(5, 20, 15), portal(30, 20, 15)].concat());
+        let nether = group_portals(portal(1, 50, 2));
+        assert_eq!(overworld.len(), 2);
+
+        let there = link_portals(&overworld, Scale::Overworld, &nether, Scale::Nether);
+        let back = link_portals(&nether, Scale::Nether, &overworld, Scale::Overworld);
+
+        assert_eq!(there[0].target, Point { x: 0, y: 20, z: 1 });
+        assert_eq!(there[0].destination.as_ref().map(|d| d.0), Some(0));
+        assert_eq!(there[1].destination.as_ref().map(|d| d.0), Some(0));
+
+        // The nether portal can only lead back to the closer of the two
+        assert_eq!(back[0].destination.as_ref().map(|d| d.0), Some(0));
+
+        let far = find_link(
+            &Point {
+                x: 400,
+                y: 64,
+                z: 0,
+            },
+            Scale::Overworld,
+            Scale::Nether,
+            &nether,
+        );
+        assert_eq!(far.destination, None);
+    }
+
+    #[test]
+    fn return_links_reach_past_the_search_radius() {
+        assert_eq!(scan_margin(Scale::Overworld), 128 + 16 * 8);
+        assert_eq!(scan_margin(Scale::Nether), 16 + 128 / 8);
+
+        let area = Bounds {
+            min: Point { x: 0, y: 0, z: 0 },
+            max: Point { x: 15, y: 0, z: 15 },
+        }
+        .columns();
+
+        // The Nether portal leads back to 128, 64, 0, where the portal at 250 is closer
+        // than the one at 0 it's linked from
+        let blocks = [portal(0, 64, 0), portal(250, 64, 0)].concat();
+        let nether = group_portals(portal(16, 64, 0));
+
+        // Scanning only the search radius past the area misses the closer portal
+        for (margin, returns_to) in [
+            (search_radius(Scale::Overworld), 0),
+            (scan_margin(Scale::Overworld), 250),
+        ] {
+            let scan = area.expand(&Point {
+                x: margin,
+                y: 0,
+                z: margin,
+            });
+            let overworld = group_portals(
+                blocks
+                    .iter()
+                    .filter(|block| scan.contains(block))
+                    .cloned()
+                    .collect(),
+            );
+
+            let there = link_portals(&overworld, Scale::Overworld, &nether, Scale::Nether);
+            let back = link_portals(&nether, Scale::Nether, &overworld, Scale::Overworld);
+
+            assert_eq!(there[0].destination.as_ref().map(|d| d.0), Some(0));
+            let (linked, _) = back[0].destination.as_ref().unwrap();
+            assert_eq!(overworld[*linked].bounds.min.x, returns_to);
+        }
+    }
+}
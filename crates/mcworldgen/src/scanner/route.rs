@@ -0,0 +1,129 @@
+use super::{distance::Metric, points::Point};
+
+/// Plan an order to visit every stop, starting from `start`
+///
+/// This is an approximate travelling salesman path: it starts with the nearest
+/// neighbor tour and then improves it with 2-opt moves until none are left.
+/// Returns the indices of the stops in the order to visit them.
+pub fn plan_route(start: &Point, stops: &[Point], metric: Metric, round_trip: bool) -> Vec<usize> {
+    let mut order = nearest_neighbor(start, stops, metric);
+
+    // The full path, including the fixed start (and end) points
+    let mut path = Vec::with_capacity(stops.len() + 2);
+    path.push(start);
+    path.extend(order.iter().map(|index| &stops[*index]));
+    if round_trip {
+        path.push(start);
+    }
+
+    let last = match round_trip {
+        true => path.len() - 2,
+        false => path.len() - 1,
+    };
+
+    let distance = |a: &Point, b: &Point| metric.distance(a, b);
+    let mut improved = true;
+
+    while improved {
+        improved = false;
+
+        for i in 1..last {
+            for j in i + 1..=last {
+                let (a, b, c) = (path[i - 1], path[i], path[j]);
+
+                // Reversing i..=j replaces the edges a-b and c-d with a-c and b-d
+                let delta = match path.get(j + 1) {
+                    Some(d) => distance(a, c) + distance(b, d) - distance(a, b) - distance(c, d),
+                    None => distance(a, c) - distance(a, b),
+                };
+
+                if delta < -1e-9 {
+                    path[i..=j].reverse();
+                    order[i - 1..j].reverse();
+                    improved = true;
+                }
+            }
+        }
+    }
+
+    order
+}
+
+fn nearest_neighbor(start: &Point, stops: &[Point], metric: Metric) -> Vec<usize> {
+    let mut visited = vec![false; stops.len()];
+    let mut order = Vec::with_capacity(stops.len());
+    let mut current = start;
+
+    while let Some(next) = (0..stops.len())
+        .filter(|index| !visited[*index])
+        .min_by(|a, b| {
+            metric
+                .distance(current, &stops[*a])
+                .total_cmp(&metric.distance(current, &stops[*b]))
+        })
+    {
+        visited[next] = true;
+        order.push(next);
+        current = &stops[next];
+    }
+
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: i64, z: i64) -> Point {
+        Point { x, y: 0, z }
+    }
+
+    fn length(start: &Point, stops: &[Point], order: &[usize]) -> f64 {
+        let mut current = start;
+        let mut total = 0.0;
+
+        for index in order {
+            total += Metric::Euclidean.distance(current, &stops[*index]);
+            current = &stops[*index];
+        }
+
+        total
+    }
+
+    #[test]
+    fn visits_every_stop() {
+        let start = point(0, 0);
+        let stops = [
+            point(10, 0),
+            point(0, 10),
+            point(10, 10),
+            point(5, 5),
+            point(-3, 0),
+        ];
+
+        let order = plan_route(&start, &stops, Metric::Euclidean, false);
+
+        let mut sorted = order.clone();
+        sorted.sort();
+        assert_eq!(sorted, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn improves_on_nearest_neighbor() {
+        let start = point(0, 0);
+        let stops = [
+            point(-2, -3),
+            point(-5, 4),
+            point(-5, 1),
+            point(4, -3),
+            point(-4, 4),
+        ];
+
+        let greedy = nearest_neighbor(&start, &stops, Metric::Euclidean);
+        let planned = plan_route(&start, &stops, Metric::Euclidean, false);
+
+        // (4, -3) -> (-2, -3) -> (-5, 1) -> (-5, 4) -> (-4, 4) is the shortest path
+        assert!(length(&start, &stops, &greedy) > 23.0);
+        assert_eq!(length(&start, &stops, &planned), 20.0);
+    }
+}
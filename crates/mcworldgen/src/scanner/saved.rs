@@ -0,0 +1,151 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use super::{blocks::BlockDescriptor, scan::ScanResult};
+
+const HEADER: &str = "# mcworldgen scan";
+
+/// Write a scan to a plain text file so it can be compared later
+///
+/// Each line is a counter (`chunks 12`), the blocks scanned at a Y level
+/// (`level -59 1024`) or a matched block (`block minecraft:diamond_ore 1 -59 3`).
+/// Block entity data isn't kept.
+pub fn save_scan(path: &Path, scan: &ScanResult) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+
+    writeln!(out, "{}", HEADER)?;
+    writeln!(out, "regions {}", scan.regions)?;
+    writeln!(out, "chunks {}", scan.chunks)?;
+    writeln!(out, "sections {}", scan.sections)?;
+    writeln!(out, "blocks {}", scan.blocks)?;
+
+    for (y, count) in &scan.blocks_by_y {
+        writeln!(out, "level {} {}", y, count)?;
+    }
+
+    for block in &scan.found {
+        writeln!(
+            out,
+            "block {} {} {} {}",
+            block.name, block.x, block.y, block.z
+        )?;
+    }
+
+    out.flush()
+}
+
+pub fn load_scan(path: &Path) -> io::Result<ScanResult> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut scan = ScanResult::new();
+
+    let invalid = |line: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: unexpected line '{}'", path.display(), line),
+        )
+    };
+
+    let mut lines = reader.lines();
+
+    if lines.next().transpose()?.as_deref() != Some(HEADER) {
+        return Err(invalid("missing header"));
+    }
+
+    for line in lines {
+        let line = line?;
+        let parts = line.split_whitespace().collect::<Vec<&str>>();
+
+        let number = |index: usize| -> io::Result<i64> {
+            parts
+                .get(index)
+                .and_then(|part| part.parse().ok())
+                .ok_or_else(|| invalid(&line))
+        };
+
+        match parts.first().copied() {
+            Some("regions") => scan.regions = number(1)? as usize,
+            Some("chunks") => scan.chunks = number(1)? as usize,
+            Some("sections") => scan.sections = number(1)? as usize,
+            Some("blocks") => scan.blocks = number(1)? as usize,
+            Some("level") => {
+                scan.blocks_by_y.insert(number(1)?, number(2)? as usize);
+            }
+            Some("block") if parts.len() == 5 => scan.found.push(BlockDescriptor::new(
+                parts[1],
+                number(2)?,
+                number(3)?,
+                number(4)?,
+            )),
+            None => {}
+            _ => return Err(invalid(&line)),
+        }
+    }
+
+    Ok(scan)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn scans_survive_a_round_trip() {
+        let mut scan = ScanResult::new();
+        scan.regions = 1;
+        scan.chunks = 4;
+        scan.sections = 96;
+        scan.blocks = 1000;
+        scan.blocks_by_y.insert(-59, 600);
+        scan.blocks_by_y.insert(-58, 400);
+        scan.blocks_by_y.insert(12, 0);
+        scan.found = vec![
+            BlockDescriptor::new("minecraft:deepslate_diamond_ore", 1, -59, 3),
+            BlockDescriptor::new("minecraft:diamond_ore", -2, -58, -4),
+        ];
+
+        let dir = std::env::temp_dir().join(format!("mcworldgen-saved-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("scan.txt");
+        save_scan(&path, &scan).unwrap();
+        let loaded = load_scan(&path).unwrap();
+
+        assert_eq!(
+            (
+                loaded.regions,
+                loaded.chunks,
+                loaded.sections,
+                loaded.blocks
+            ),
+            (1, 4, 96, 1000)
+        );
+        assert_eq!(loaded.blocks_by_y, scan.blocks_by_y);
+        assert_eq!(loaded.found, scan.found);
+
+        // The raw names are kept and the variants share their group again
+        assert_eq!(loaded.found[0].name, "minecraft:deepslate_diamond_ore");
+        assert!(loaded
+            .found
+            .iter()
+            .all(|block| block.group == "minecraft:diamond_ore"));
+
+        // Files without the header, or with lines that aren't understood, are refused
+        let headless = dir.join("headless.txt");
+        fs::write(&headless, "chunks 4\n").unwrap();
+        assert_eq!(
+            load_scan(&headless).err().map(|err| err.kind()),
+            Some(io::ErrorKind::InvalidData)
+        );
+
+        let garbled = dir.join("garbled.txt");
+        fs::write(&garbled, format!("{}\nchunks four\n", HEADER)).unwrap();
+        assert!(load_scan(&garbled).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,12 +1,108 @@
+use std::{collections::BTreeMap, fs::File, io, path::Path};
+
+use fastanvil::{Region, RegionFileLoader, RegionLoader};
+use fastnbt::from_bytes;
 use itertools::Itertools;
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+use serde::de::DeserializeOwned;
+
+use super::{blocks::BlockDescriptor, bounds::Bounds, shapes::Shape};
+
+/// Run `scan` in parallel on every region file of a dimension that overlaps the area
+///
+/// Also returns how many region files were skipped without being read.
+pub fn scan_regions<T, F>(path: &Path, area: &dyn Shape, scan: F) -> io::Result<(Vec<T>, usize)>
+where
+    T: Send,
+    F: Fn(&mut Region<File>, i64, i64) -> T + Send + Sync,
+{
+    scan_region_files(&path.join("region"), area, scan)
+}
+
+/// Run `scan` in parallel on every region file in a directory that overlaps the area
+///
+/// Entities are kept in their own region files, next to the ones holding blocks.
+pub fn scan_region_files<T, F>(dir: &Path, area: &dyn Shape, scan: F) -> io::Result<(Vec<T>, usize)>
+where
+    T: Send,
+    F: Fn(&mut Region<File>, i64, i64) -> T + Send + Sync,
+{
+    let loader = RegionFileLoader::new(dir.to_path_buf());
+    let regions = loader
+        .list()
+        .map_err(|err| io::Error::new(io::ErrorKind::NotFound, err.to_string()))?;
+    let regions_len = regions.len();
+
+    // Only regions that overlap the search area need to be read at all
+    let regions = regions
+        .into_iter()
+        .filter(|(rx, rz)| area.intersects(&Bounds::of_region(rx.0 as i64, rz.0 as i64)))
+        .collect_vec();
+
+    let skipped = regions_len - regions.len();
+
+    let results = regions
+        .into_par_iter()
+        .filter_map(|(rx, rz)| {
+            let mut file = loader.region(rx, rz).ok().flatten()?;
+
+            Some(scan(&mut file, rx.0 as i64, rz.0 as i64))
+        })
+        .collect();
+
+    Ok((results, skipped))
+}
+
+/// Deserialize every chunk of a region that overlaps the area and collect what `read` makes of them
+///
+/// Chunks that are missing or can't be deserialized into `C` are skipped.
+pub fn read_chunks<C, T, F>(
+    region: &mut Region<File>,
+    rx: i64,
+    rz: i64,
+    area: &dyn Shape,
+    read: F,
+) -> Vec<T>
+where
+    C: DeserializeOwned,
+    F: Fn(C) -> Vec<T>,
+{
+    let mut found = vec![];
+
+    for (cx, cz) in (0..32i64).flat_map(|z| (0..32i64).map(move |x| (x, z))) {
+        if !area.intersects(&Bounds::of_chunk(rx * 32 + cx, rz * 32 + cz)) {
+            continue;
+        }
 
-use super::blocks::BlockDescriptor;
+        let Ok(Some(data)) = region.read_chunk(cx as usize, cz as usize) else {
+            continue;
+        };
+
+        if let Ok(chunk) = from_bytes::<C>(&data) {
+            found.extend(read(chunk));
+        }
+    }
+
+    found
+}
 
 pub struct ScanResult {
     pub regions: usize,
     pub chunks: usize,
     pub sections: usize,
     pub blocks: usize,
+
+    // Non-air blocks scanned at each Y level
+    pub blocks_by_y: BTreeMap<i64, usize>,
+
+    // Parts of the dimension that were never read because they fall outside the search bounds
+    pub skipped_regions: usize,
+    pub skipped_chunks: usize,
+    pub skipped_sections: usize,
+
+    // Sections whose palette has no entry matching the search
+    pub unmatched_sections: usize,
+
     pub found: Vec<BlockDescriptor>,
 }
 
@@ -17,6 +113,11 @@ impl ScanResult {
             chunks: 0,
             sections: 0,
             blocks: 0,
+            blocks_by_y: BTreeMap::new(),
+            skipped_regions: 0,
+            skipped_chunks: 0,
+            skipped_sections: 0,
+            unmatched_sections: 0,
             found: vec![],
         }
     }
@@ -29,6 +130,14 @@ impl ScanResult {
             summary.chunks += result.chunks;
             summary.sections += result.sections;
             summary.blocks += result.blocks;
+            summary.skipped_regions += result.skipped_regions;
+            summary.skipped_chunks += result.skipped_chunks;
+            summary.skipped_sections += result.skipped_sections;
+            summary.unmatched_sections += result.unmatched_sections;
+
+            for (y, count) in &result.blocks_by_y {
+                *summary.blocks_by_y.entry(*y).or_default() += count;
+            }
 
             let mut found = result.found.clone();
             summary.found.append(&mut found);
@@ -37,6 +146,14 @@ impl ScanResult {
         summary
     }
 
+    /// Record the non-air blocks in each layer of a section starting at the given Y
+    pub fn count_blocks(&mut self, sy: i64, layers: &[usize; 16]) {
+        for (y, count) in layers.iter().enumerate() {
+            self.blocks += count;
+            *self.blocks_by_y.entry(sy + y as i64).or_default() += count;
+        }
+    }
+
     #[inline(always)]
     pub fn filtering_blocks<P>(self, predicate: P) -> Self
     where
@@ -57,6 +174,11 @@ impl ScanResult {
             chunks: self.chunks,
             sections: self.sections,
             blocks: self.blocks,
+            blocks_by_y: self.blocks_by_y,
+            skipped_regions: self.skipped_regions,
+            skipped_chunks: self.skipped_chunks,
+            skipped_sections: self.skipped_sections,
+            unmatched_sections: self.unmatched_sections,
             found: mapper(self.found).into_iter().collect_vec(),
         }
     }
@@ -0,0 +1,384 @@
+use std::fmt::Display;
+
+use itertools::Itertools;
+
+use super::{bounds::Bounds, points::Point};
+
+/// A volume of the world that a scan is limited to
+pub trait Shape: Send + Sync {
+    fn contains(&self, point: &Point) -> bool;
+
+    /// The smallest box that encloses the whole shape
+    fn bounds(&self) -> Bounds;
+
+    /// Whether any part of the shape may lie inside the given box
+    ///
+    /// This is used to skip regions, chunks and sections before reading them so it
+    /// may report false positives but never false negatives.
+    fn intersects(&self, area: &Bounds) -> bool {
+        self.bounds().intersects(area)
+    }
+
+    /// Whether the given box lies completely inside the shape
+    ///
+    /// The opposite of `intersects`: false negatives are fine, false positives are not.
+    fn covers(&self, _area: &Bounds) -> bool {
+        false
+    }
+}
+
+impl Shape for Bounds {
+    fn contains(&self, point: &Point) -> bool {
+        Bounds::contains(self, point)
+    }
+
+    fn bounds(&self) -> Bounds {
+        self.clone()
+    }
+
+    fn intersects(&self, area: &Bounds) -> bool {
+        Bounds::intersects(self, area)
+    }
+
+    fn covers(&self, area: &Bounds) -> bool {
+        self.contains(&area.min) && self.contains(&area.max)
+    }
+}
+
+pub struct Sphere {
+    pub center: Point,
+    pub radius: i64,
+}
+
+impl Shape for Sphere {
+    fn contains(&self, point: &Point) -> bool {
+        let dx = point.x - self.center.x;
+        let dy = point.y - self.center.y;
+        let dz = point.z - self.center.z;
+
+        dx * dx + dy * dy + dz * dz <= self.radius * self.radius
+    }
+
+    fn bounds(&self) -> Bounds {
+        Bounds::from_point(&self.center).expand(&Point::from(self.radius))
+    }
+
+    fn intersects(&self, area: &Bounds) -> bool {
+        self.contains(&area.closest_to(&self.center))
+    }
+
+    fn covers(&self, area: &Bounds) -> bool {
+        area.corners().iter().all(|corner| self.contains(corner))
+    }
+}
+
+/// A vertical cylinder standing on the XZ plane
+pub struct Cylinder {
+    pub x: i64,
+    pub z: i64,
+    pub radius: i64,
+    pub min_y: i64,
+    pub max_y: i64,
+}
+
+impl Cylinder {
+    fn contains_column(&self, x: i64, z: i64) -> bool {
+        let dx = x - self.x;
+        let dz = z - self.z;
+
+        dx * dx + dz * dz <= self.radius * self.radius
+    }
+}
+
+impl Shape for Cylinder {
+    fn contains(&self, point: &Point) -> bool {
+        point.y >= self.min_y && point.y <= self.max_y && self.contains_column(point.x, point.z)
+    }
+
+    fn bounds(&self) -> Bounds {
+        Bounds {
+            min: Point {
+                x: self.x - self.radius,
+                y: self.min_y,
+                z: self.z - self.radius,
+            },
+            max: Point {
+                x: self.x + self.radius,
+                y: self.max_y,
+                z: self.z + self.radius,
+            },
+        }
+    }
+
+    fn intersects(&self, area: &Bounds) -> bool {
+        let closest = area.closest_to(&Point {
+            x: self.x,
+            y: self.min_y,
+            z: self.z,
+        });
+
+        self.bounds().intersects(area) && self.contains_column(closest.x, closest.z)
+    }
+
+    fn covers(&self, area: &Bounds) -> bool {
+        area.min.y >= self.min_y
+            && area.max.y <= self.max_y
+            && area
+                .corners()
+                .iter()
+                .all(|corner| self.contains_column(corner.x, corner.z))
+    }
+}
+
+/// An arbitrary polygon on the XZ plane extruded over a range of Y levels
+pub struct Polygon {
+    pub points: Vec<(i64, i64)>,
+    pub min_y: i64,
+    pub max_y: i64,
+}
+
+impl Polygon {
+    // Even-odd ray casting, sampling the center of each block column
+    fn contains_column(&self, x: i64, z: i64) -> bool {
+        let (px, pz) = (x as f64 + 0.5, z as f64 + 0.5);
+        let mut inside = false;
+
+        for ((ax, az), (bx, bz)) in self.points.iter().circular_tuple_windows() {
+            let (ax, az, bx, bz) = (*ax as f64, *az as f64, *bx as f64, *bz as f64);
+
+            if (az > pz) != (bz > pz) && px < ax + (pz - az) * (bx - ax) / (bz - az) {
+                inside = !inside;
+            }
+        }
+
+        inside
+    }
+}
+
+impl Shape for Polygon {
+    fn contains(&self, point: &Point) -> bool {
+        point.y >= self.min_y && point.y <= self.max_y && self.contains_column(point.x, point.z)
+    }
+
+    fn bounds(&self) -> Bounds {
+        let (min_x, max_x) = self
+            .points
+            .iter()
+            .map(|(x, _)| *x)
+            .minmax()
+            .into_option()
+            .unwrap_or((0, 0));
+        let (min_z, max_z) = self
+            .points
+            .iter()
+            .map(|(_, z)| *z)
+            .minmax()
+            .into_option()
+            .unwrap_or((0, 0));
+
+        Bounds {
+            min: Point {
+                x: min_x,
+                y: self.min_y,
+                z: min_z,
+            },
+            max: Point {
+                x: max_x,
+                y: self.max_y,
+                z: max_z,
+            },
+        }
+    }
+}
+
+/// Every point that is inside at least one of the shapes
+pub struct Union(pub Vec<Box<dyn Shape>>);
+
+impl Shape for Union {
+    fn contains(&self, point: &Point) -> bool {
+        self.0.iter().any(|shape| shape.contains(point))
+    }
+
+    fn bounds(&self) -> Bounds {
+        self.0
+            .iter()
+            .map(|shape| shape.bounds())
+            .reduce(|a, b| a.union(&b))
+            .unwrap_or(Bounds::ZERO)
+    }
+
+    fn intersects(&self, area: &Bounds) -> bool {
+        self.0.iter().any(|shape| shape.intersects(area))
+    }
+
+    fn covers(&self, area: &Bounds) -> bool {
+        self.0.iter().any(|shape| shape.covers(area))
+    }
+}
+
+/// Every point inside the first shape that isn't inside the second
+pub struct Difference(pub Box<dyn Shape>, pub Box<dyn Shape>);
+
+impl Shape for Difference {
+    fn contains(&self, point: &Point) -> bool {
+        self.0.contains(point) && !self.1.contains(point)
+    }
+
+    fn bounds(&self) -> Bounds {
+        self.0.bounds()
+    }
+
+    fn intersects(&self, area: &Bounds) -> bool {
+        self.0.intersects(area) && !self.1.covers(area)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ShapeParseError(pub String);
+
+impl Display for ShapeParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid area: {}", self.0)
+    }
+}
+
+/// Parse an area expression like `sphere:0,64,0:64 - box:-16,-64,-16:16,320,16`
+///
+/// Shapes are combined left to right with `+` (union) and `-` (difference):
+///
+/// - `box:X,Y,Z:X,Y,Z` — the two opposite corners of a box
+/// - `sphere:X,Y,Z:R`
+/// - `cylinder:X,Z:R:MIN_Y,MAX_Y`
+/// - `polygon:MIN_Y,MAX_Y:X,Z:X,Z:X,Z...`
+pub fn parse_area(expr: &str) -> Result<Box<dyn Shape>, ShapeParseError> {
+    let mut tokens = expr.split_whitespace();
+
+    let mut area = parse_shape(
+        tokens
+            .next()
+            .ok_or_else(|| ShapeParseError("empty expression".to_string()))?,
+    )?;
+
+    while let Some(op) = tokens.next() {
+        let shape = parse_shape(
+            tokens
+                .next()
+                .ok_or_else(|| ShapeParseError(format!("expected a shape after '{}'", op)))?,
+        )?;
+
+        area = match op {
+            "+" => Box::new(Union(vec![area, shape])),
+            "-" => Box::new(Difference(area, shape)),
+            _ => return Err(ShapeParseError(format!("unknown operator '{}'", op))),
+        };
+    }
+
+    Ok(area)
+}
+
+fn parse_shape(spec: &str) -> Result<Box<dyn Shape>, ShapeParseError> {
+    let mut parts = spec.split(':');
+    let kind = parts.next().unwrap_or_default();
+
+    let groups = parts
+        .map(|group| {
+            group
+                .split(',')
+                .map(|n| n.trim().parse::<i64>())
+                .collect::<Result<Vec<i64>, _>>()
+        })
+        .collect::<Result<Vec<Vec<i64>>, _>>()
+        .map_err(|err| ShapeParseError(format!("{} in '{}'", err, spec)))?;
+
+    let sizes = groups.iter().map(|group| group.len()).collect_vec();
+    let invalid = || ShapeParseError(format!("malformed {} '{}'", kind, spec));
+
+    let shape: Box<dyn Shape> = match (kind, sizes.as_slice()) {
+        ("box", [3, 3]) => {
+            let a = point(&groups[0]);
+            let b = point(&groups[1]);
+
+            Box::new(Bounds::from_point(&a).union(&Bounds::from_point(&b)))
+        }
+        ("sphere", [3, 1]) => Box::new(Sphere {
+            center: point(&groups[0]),
+            radius: groups[1][0],
+        }),
+        ("cylinder", [2, 1, 2]) => Box::new(Cylinder {
+            x: groups[0][0],
+            z: groups[0][1],
+            radius: groups[1][0],
+            min_y: groups[2][0],
+            max_y: groups[2][1],
+        }),
+        ("polygon", [2, rest @ ..]) if rest.len() >= 3 && rest.iter().all(|n| *n == 2) => {
+            Box::new(Polygon {
+                min_y: groups[0][0],
+                max_y: groups[0][1],
+                points: groups[1..].iter().map(|p| (p[0], p[1])).collect(),
+            })
+        }
+        ("box" | "sphere" | "cylinder" | "polygon", _) => return Err(invalid()),
+        _ => return Err(ShapeParseError(format!("unknown shape '{}'", kind))),
+    };
+
+    Ok(shape)
+}
+
+fn point(group: &[i64]) -> Point {
+    Point {
+        x: group[0],
+        y: group[1],
+        z: group[2],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sphere_minus_box() {
+        let area = parse_area("sphere:0,64,0:64 - box:-16,-64,-16:16,320,16").unwrap();
+
+        assert!(area.contains(&Point { x: 40, y: 64, z: 0 }));
+        assert!(!area.contains(&Point { x: 0, y: 64, z: 0 }));
+        assert!(!area.contains(&Point {
+            x: 60,
+            y: 64,
+            z: 60
+        }));
+
+        // The spawn chunk is entirely inside the hole so it can be skipped
+        assert!(!area.intersects(&Bounds::of_section(0, 4, 0)));
+        assert!(area.intersects(&Bounds::of_section(2, 4, 0)));
+        assert!(!area.intersects(&Bounds::of_region(1, 0)));
+    }
+
+    #[test]
+    fn polygon_and_cylinder() {
+        let claim = parse_area("polygon:-64,320:0,0:100,0:100,50:50,100:0,100").unwrap();
+
+        assert!(claim.contains(&Point { x: 10, y: 0, z: 10 }));
+        assert!(!claim.contains(&Point { x: 95, y: 0, z: 95 }));
+        assert!(!claim.contains(&Point {
+            x: 10,
+            y: 400,
+            z: 10
+        }));
+
+        let tower = parse_area("cylinder:0,0:10:0,100").unwrap();
+
+        assert!(tower.contains(&Point { x: 6, y: 50, z: 8 }));
+        assert!(!tower.contains(&Point { x: 8, y: 50, z: 8 }));
+        assert!(!tower.intersects(&Bounds::of_chunk(1, 1)));
+    }
+
+    #[test]
+    fn rejects_bad_expressions() {
+        assert!(parse_area("").is_err());
+        assert!(parse_area("sphere:0,0,0").is_err());
+        assert!(parse_area("cube:0,0,0:1").is_err());
+        assert!(parse_area("sphere:0,0,0:5 *").is_err());
+    }
+}
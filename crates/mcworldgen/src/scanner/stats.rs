@@ -0,0 +1,162 @@
+use itertools::Itertools;
+
+pub fn mean(values: &[f64]) -> Option<f64> {
+    match values.is_empty() {
+        true => None,
+        false => Some(values.iter().sum::<f64>() / values.len() as f64),
+    }
+}
+
+/// The value below which the given fraction of the (sorted) values fall
+pub fn quantile(sorted: &[f64], q: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+
+    let index = ((sorted.len() - 1) as f64 * q).round() as usize;
+
+    Some(sorted[index])
+}
+
+/// The result of a significance test
+pub struct Significance {
+    /// The test statistic (a z-score or the KS distance)
+    pub statistic: f64,
+
+    /// The probability of seeing a difference at least this large by chance alone
+    pub p: f64,
+}
+
+/// Compare two event counts observed over different amounts of exposure (e.g. chunks scanned)
+///
+/// Conditional on the total, the first count is binomial with a probability
+/// proportional to its exposure. This uses the normal approximation of that test.
+pub fn compare_rates(a: u64, a_exposure: f64, b: u64, b_exposure: f64) -> Significance {
+    let n = (a + b) as f64;
+    let p = a_exposure / (a_exposure + b_exposure);
+
+    if n == 0.0 || p <= 0.0 || p >= 1.0 {
+        return Significance {
+            statistic: 0.0,
+            p: 1.0,
+        };
+    }
+
+    let z = (a as f64 - n * p) / (n * p * (1.0 - p)).sqrt();
+
+    Significance {
+        statistic: z,
+        p: erfc(z.abs() / std::f64::consts::SQRT_2),
+    }
+}
+
+/// Two-sample Kolmogorov–Smirnov test of whether two samples come from the same distribution
+pub fn compare_distributions(a: &[f64], b: &[f64]) -> Significance {
+    if a.is_empty() || b.is_empty() {
+        return Significance {
+            statistic: 0.0,
+            p: 1.0,
+        };
+    }
+
+    let a = a.iter().copied().sorted_by(f64::total_cmp).collect_vec();
+    let b = b.iter().copied().sorted_by(f64::total_cmp).collect_vec();
+
+    let (mut i, mut j, mut d) = (0, 0, 0.0f64);
+
+    while i < a.len() && j < b.len() {
+        let value = a[i].min(b[j]);
+
+        while i < a.len() && a[i] <= value {
+            i += 1;
+        }
+        while j < b.len() && b[j] <= value {
+            j += 1;
+        }
+
+        d = d.max((i as f64 / a.len() as f64 - j as f64 / b.len() as f64).abs());
+    }
+
+    let n = (a.len() * b.len()) as f64 / (a.len() + b.len()) as f64;
+    let lambda = (n.sqrt() + 0.12 + 0.11 / n.sqrt()) * d;
+
+    Significance {
+        statistic: d,
+        p: kolmogorov_q(lambda),
+    }
+}
+
+// The complementary Kolmogorov distribution
+fn kolmogorov_q(lambda: f64) -> f64 {
+    if lambda < 1e-3 {
+        return 1.0;
+    }
+
+    let sum = (1..=100)
+        .map(|k| {
+            let k = k as f64;
+            let sign = if k as i64 % 2 == 1 { 1.0 } else { -1.0 };
+
+            sign * (-2.0 * k * k * lambda * lambda).exp()
+        })
+        .sum::<f64>();
+
+    (2.0 * sum).clamp(0.0, 1.0)
+}
+
+// Complementary error function (Numerical Recipes' Chebyshev approximation)
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+
+    let r = t
+        * (-z * z - 1.26551223
+            + t * (1.00002368
+                + t * (0.37409196
+                    + t * (0.09678418
+                        + t * (-0.18628806
+                            + t * (0.27886807
+                                + t * (-1.13520398
+                                    + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277)))))))))
+            .exp();
+
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates_normalized_by_exposure() {
+        // Twice the count over twice the chunks is no change at all
+        let same = compare_rates(200, 100.0, 100, 50.0);
+        assert!(same.statistic.abs() < 1e-9);
+        assert!((same.p - 1.0).abs() < 1e-6);
+
+        let more = compare_rates(300, 100.0, 100, 100.0);
+        assert!(more.statistic > 5.0);
+        assert!(more.p < 0.001);
+    }
+
+    #[test]
+    fn distributions() {
+        let a = (0..100).map(|y| y as f64).collect_vec();
+        let shifted = (0..100).map(|y| y as f64 + 50.0).collect_vec();
+
+        let same = compare_distributions(&a, &a);
+        assert_eq!(same.statistic, 0.0);
+        assert!(same.p > 0.99);
+
+        let different = compare_distributions(&a, &shifted);
+        assert!((different.statistic - 0.5).abs() < 1e-9);
+        assert!(different.p < 0.001);
+
+        assert_eq!(quantile(&a, 0.5), Some(50.0));
+        assert_eq!(quantile(&a, 0.9), Some(89.0));
+    }
+}
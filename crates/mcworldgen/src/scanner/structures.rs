@@ -0,0 +1,166 @@
+use std::{collections::HashMap, path::Path};
+
+use fastnbt::{from_bytes, IntArray, LongArray};
+use serde::Deserialize;
+
+use super::{lookup::ChunkReader, points::Point};
+
+#[derive(Deserialize, Debug)]
+struct ChunkStructures {
+    structures: Option<Structures>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Structures {
+    /// The chunks (packed into longs) holding the start of each structure that reaches this chunk
+    #[serde(rename = "References", default)]
+    references: HashMap<String, LongArray>,
+
+    #[serde(default)]
+    starts: HashMap<String, StructureStart>,
+}
+
+#[derive(Deserialize, Debug)]
+struct StructureStart {
+    #[serde(rename = "Children", default)]
+    children: Vec<StructurePiece>,
+}
+
+#[derive(Deserialize, Debug)]
+struct StructurePiece {
+    /// min x, y, z followed by max x, y, z
+    #[serde(rename = "BB")]
+    bounds: Option<IntArray>,
+}
+
+/// Finds the generated structures (mineshafts, fortresses, ...) a position is part of
+///
+/// The structures of each chunk are read once and cached, like `BlockLookup` does
+/// with sections, since spawners of the same structure share their chunks.
+pub struct StructureLookup {
+    chunks: ChunkReader,
+    structures: HashMap<(i64, i64), Option<Structures>>,
+}
+
+impl StructureLookup {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            chunks: ChunkReader::new(path),
+            structures: HashMap::new(),
+        }
+    }
+
+    /// The ids of every structure with a piece that contains the position
+    pub fn structures_at(&mut self, point: &Point) -> Vec<String> {
+        let (cx, cz) = (point.x.div_euclid(16), point.z.div_euclid(16));
+
+        let Some(references) = self.structures(cx, cz).map(|s| s.references.clone()) else {
+            return vec![];
+        };
+
+        let mut found = vec![];
+
+        for (id, chunks) in references {
+            for packed in chunks.iter().copied() {
+                // The low 32 bits are the chunk's x and the high 32 bits its z
+                let (sx, sz) = (packed as i32 as i64, packed >> 32);
+
+                let contains = self
+                    .structures(sx, sz)
+                    .and_then(|structures| structures.starts.get(&id))
+                    .is_some_and(|start| start.children.iter().any(|piece| piece.contains(point)));
+
+                if contains && !found.contains(&id) {
+                    found.push(id.clone());
+                }
+            }
+        }
+
+        found.sort();
+        found
+    }
+
+    fn structures(&mut self, cx: i64, cz: i64) -> Option<&Structures> {
+        let chunks = &mut self.chunks;
+
+        self.structures
+            .entry((cx, cz))
+            .or_insert_with(|| {
+                let data = chunks.read(cx, cz)?;
+                from_bytes::<ChunkStructures>(&data).ok()?.structures
+            })
+            .as_ref()
+    }
+}
+
+impl StructurePiece {
+    fn contains(&self, point: &Point) -> bool {
+        match self.bounds.as_deref() {
+            Some([min_x, min_y, min_z, max_x, max_y, max_z]) => {
+                (*min_x as i64..=*max_x as i64).contains(&point.x)
+                    && (*min_y as i64..=*max_y as i64).contains(&point.y)
+                    && (*min_z as i64..=*max_z as i64).contains(&point.z)
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn piece(bounds: [i32; 6]) -> StructurePiece {
+        StructurePiece {
+            bounds: Some(IntArray::new(bounds.to_vec())),
+        }
+    }
+
+    #[test]
+    fn pieces_are_found_through_references() {
+        let mut lookup = StructureLookup::new(Path::new("missing"));
+
+        // A mineshaft starting in chunk -1, 2 that reaches into chunk 0, 0
+        let start = (2i64 << 32) | (-1i32 as u32 as i64);
+
+        lookup.structures.insert(
+            (0, 0),
+            Some(Structures {
+                references: HashMap::from([
+                    (
+                        "minecraft:mineshaft".to_string(),
+                        LongArray::new(vec![start]),
+                    ),
+                    // Referenced, but its start is missing
+                    ("minecraft:fortress".to_string(), LongArray::new(vec![0])),
+                ]),
+                starts: HashMap::new(),
+            }),
+        );
+        lookup.structures.insert(
+            (-1, 2),
+            Some(Structures {
+                references: HashMap::new(),
+                starts: HashMap::from([(
+                    "minecraft:mineshaft".to_string(),
+                    StructureStart {
+                        children: vec![
+                            piece([-20, 10, -5, 5, 20, 40]),
+                            piece([-20, 30, -5, 5, 32, 40]),
+                            StructurePiece { bounds: None },
+                        ],
+                    },
+                )]),
+            }),
+        );
+
+        let at = |lookup: &mut StructureLookup, x, y, z| lookup.structures_at(&Point { x, y, z });
+
+        assert_eq!(at(&mut lookup, 3, 15, 3), vec!["minecraft:mineshaft"]);
+        assert_eq!(at(&mut lookup, 3, 31, 3), vec!["minecraft:mineshaft"]);
+
+        // Between the pieces, or in a chunk without references
+        assert!(at(&mut lookup, 3, 25, 3).is_empty());
+        assert!(at(&mut lookup, 3, 15, 100).is_empty());
+    }
+}
@@ -0,0 +1,745 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+use fastanvil::CurrentJavaChunk;
+use fastnbt::{from_bytes, ByteArray};
+use flate2::read::GzDecoder;
+use itertools::Itertools;
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+use serde::Deserialize;
+
+use super::{
+    blocks::BlockPatterns,
+    bounds::Bounds,
+    census::AIR_BLOCKS,
+    lookup::BlockLookup,
+    points::Point,
+    scan::{read_chunks, scan_regions},
+    shapes::Shape,
+};
+
+/// One block of a template: where it is and what may be there
+#[derive(Clone, Debug)]
+pub struct Cell {
+    pub offset: Point,
+    pub blocks: BlockPatterns,
+}
+
+impl Cell {
+    fn pattern(&self) -> String {
+        self.blocks.0.iter().map(|(pattern, _)| pattern).join(",")
+    }
+}
+
+/// A small arrangement of blocks to look for, e.g. a desert well
+///
+/// Cells that may hold anything are left out, so a template only lists the blocks that matter.
+#[derive(Clone, Debug)]
+pub struct Template {
+    pub cells: Vec<Cell>,
+}
+
+#[derive(Debug)]
+pub struct TemplateError(pub String);
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// Air in a structure file should also match the cave air a cave leaves behind
+const AIR: &str = "minecraft:air,minecraft:cave_air,minecraft:void_air";
+
+impl Template {
+    /// Parse an inline template, e.g. `S=*sandstone W=minecraft:water SSS/SWS/SSS S.S/.../S.S`
+    ///
+    /// Words with `=` give a character the blocks it stands for. The other words are
+    /// layers from the bottom up, each made of rows along Z separated by `/` with one
+    /// character per block along X. `.` matches any block.
+    pub fn parse(template: &str) -> Result<Self, TemplateError> {
+        let mut legend = HashMap::new();
+        let mut layers = vec![];
+
+        for word in template.split_whitespace() {
+            match word.split_once('=') {
+                Some((key, blocks)) => {
+                    let mut chars = key.chars();
+                    let (Some(key), None) = (chars.next(), chars.next()) else {
+                        return Err(TemplateError(format!(
+                            "'{}' should name a single character",
+                            key
+                        )));
+                    };
+
+                    legend.insert(key, BlockPatterns::parse(blocks));
+                }
+                None => layers.push(word),
+            }
+        }
+
+        let mut cells = vec![];
+
+        for (y, layer) in layers.iter().enumerate() {
+            for (z, row) in layer.split('/').enumerate() {
+                for (x, key) in row.chars().enumerate() {
+                    if key == '.' {
+                        continue;
+                    }
+
+                    let blocks = legend.get(&key).ok_or_else(|| {
+                        TemplateError(format!("'{}' isn't in the template's legend", key))
+                    })?;
+
+                    cells.push(Cell {
+                        offset: Point {
+                            x: x as i64,
+                            y: y as i64,
+                            z: z as i64,
+                        },
+                        blocks: blocks.clone(),
+                    });
+                }
+            }
+        }
+
+        Self::new(cells)
+    }
+
+    /// Read a template from a structure block (`.nbt`) or Sponge schematic (`.schem`) file
+    ///
+    /// Block states are ignored. Structure voids match any block and air matches cave air too.
+    pub fn read(path: &Path) -> Result<Self, TemplateError> {
+        let error = |err: &dyn fmt::Display| TemplateError(format!("{}: {}", path.display(), err));
+
+        let mut data = vec![];
+        GzDecoder::new(File::open(path).map_err(|err| error(&err))?)
+            .read_to_end(&mut data)
+            .map_err(|err| error(&err))?;
+
+        let blocks = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("nbt") => structure_blocks(&data),
+            Some("schem") => schematic_blocks(&data),
+            _ => Err("expected a .nbt or .schem file".to_string()),
+        }
+        .map_err(|err| error(&err))?;
+
+        let cells = blocks
+            .into_iter()
+            .filter(|(_, name)| name != "minecraft:structure_void")
+            .map(|(offset, name)| Cell {
+                offset,
+                blocks: BlockPatterns::parse(match name.as_str() {
+                    "minecraft:air" => AIR,
+                    name => name,
+                }),
+            })
+            .collect();
+
+        Self::new(cells)
+    }
+
+    fn new(cells: Vec<Cell>) -> Result<Self, TemplateError> {
+        if cells.is_empty() {
+            return Err(TemplateError("the template has no blocks".to_string()));
+        }
+
+        Ok(Self { cells }.normalized())
+    }
+
+    // Move the template so its smallest coordinates are 0 and order its cells
+    fn normalized(mut self) -> Self {
+        let min = |axis: fn(&Point) -> i64| {
+            self.cells
+                .iter()
+                .map(|cell| axis(&cell.offset))
+                .min()
+                .unwrap_or(0)
+        };
+        let (x, y, z) = (min(|p| p.x), min(|p| p.y), min(|p| p.z));
+
+        for cell in &mut self.cells {
+            cell.offset = Point {
+                x: cell.offset.x - x,
+                y: cell.offset.y - y,
+                z: cell.offset.z - z,
+            };
+        }
+
+        self.cells
+            .sort_by_key(|cell| (cell.offset.y, cell.offset.z, cell.offset.x));
+        self
+    }
+
+    pub fn size(&self) -> Point {
+        let max = |axis: fn(&Point) -> i64| {
+            self.cells
+                .iter()
+                .map(|cell| axis(&cell.offset))
+                .max()
+                .unwrap_or(0)
+                + 1
+        };
+
+        Point {
+            x: max(|p| p.x),
+            y: max(|p| p.y),
+            z: max(|p| p.z),
+        }
+    }
+
+    /// The template turned clockwise (seen from above) by a number of quarter turns
+    /// after optionally mirroring it along X
+    fn turned(&self, quarter_turns: u8, mirrored: bool) -> Self {
+        let cells = self
+            .cells
+            .iter()
+            .map(|cell| {
+                let Point { mut x, y, mut z } = cell.offset;
+
+                if mirrored {
+                    x = -x;
+                }
+
+                for _ in 0..quarter_turns {
+                    (x, z) = (-z, x);
+                }
+
+                Cell {
+                    offset: Point { x, y, z },
+                    blocks: cell.blocks.clone(),
+                }
+            })
+            .collect();
+
+        Self { cells }.normalized()
+    }
+
+    /// Every distinct way the template may be placed
+    ///
+    /// Symmetric templates look the same after some turns, which are only listed once.
+    pub fn orientations(&self, rotate: bool, mirror: bool) -> Vec<Orientation> {
+        let turns = if rotate { 0..4 } else { 0..1 };
+        let mirrors: &[bool] = if mirror { &[false, true] } else { &[false] };
+        let mut seen = HashSet::new();
+
+        mirrors
+            .iter()
+            .flat_map(|mirrored| turns.clone().map(move |turns| (turns, *mirrored)))
+            .filter_map(|(turns, mirrored)| {
+                let template = self.turned(turns, mirrored);
+                let key = template
+                    .cells
+                    .iter()
+                    .map(|cell| (cell.offset.clone(), cell.pattern()))
+                    .collect_vec();
+
+                seen.insert(key).then_some(Orientation {
+                    degrees: turns as u16 * 90,
+                    mirrored,
+                    template,
+                })
+            })
+            .collect()
+    }
+
+    /// The cells that could be looked for first, one per distinct pattern
+    ///
+    /// Air can't be looked for, as `locate` skips it, so templates of nothing but air
+    /// (or cells that match anything) have no anchor at all.
+    pub fn anchors(&self) -> Result<Vec<&Cell>, TemplateError> {
+        let anchors = self
+            .cells
+            .iter()
+            .filter(|cell| !AIR_BLOCKS.iter().any(|air| cell.blocks.matches(air)))
+            .unique_by(|cell| cell.pattern())
+            .collect_vec();
+
+        match anchors.is_empty() {
+            true => Err(TemplateError(
+                "the template needs at least one block that isn't air".to_string(),
+            )),
+            false => Ok(anchors),
+        }
+    }
+}
+
+/// A template placed in one direction
+#[derive(Clone, Debug)]
+pub struct Orientation {
+    pub degrees: u16,
+    pub mirrored: bool,
+    pub template: Template,
+}
+
+/// Where a template was found
+#[derive(Clone, Debug)]
+pub struct TemplateMatch {
+    pub bounds: Bounds,
+    pub degrees: u16,
+    pub mirrored: bool,
+}
+
+/// How many sections in the chunks of the area have a palette entry matching each of the patterns
+///
+/// Only palettes are read, which makes this a cheap way to tell how rare blocks are.
+pub fn count_palettes(
+    path: &Path,
+    area: &dyn Shape,
+    patterns: &[&BlockPatterns],
+) -> io::Result<Vec<usize>> {
+    let (counts, _) = scan_regions(path, area, |region, rx, rz| {
+        read_chunks(region, rx, rz, area, |chunk: CurrentJavaChunk| {
+            let Some(tower) = chunk.sections else {
+                return vec![];
+            };
+
+            tower
+                .sections()
+                .iter()
+                .map(|section| {
+                    let palette = section.block_states.palette();
+
+                    patterns
+                        .iter()
+                        .map(|patterns| {
+                            palette.iter().any(|block| patterns.matches(block.name())) as usize
+                        })
+                        .collect_vec()
+                })
+                .collect()
+        })
+    })?;
+
+    let mut totals = vec![0; patterns.len()];
+
+    for section in counts.into_iter().flatten() {
+        for (total, count) in totals.iter_mut().zip(section) {
+            *total += count;
+        }
+    }
+
+    Ok(totals)
+}
+
+/// Check every orientation of the template against the blocks around each anchor position
+///
+/// `anchors` are the positions of blocks matching the `anchor` cell of the template.
+pub fn find_template(
+    path: &Path,
+    anchor: &Cell,
+    orientations: &[Orientation],
+    anchors: Vec<Point>,
+) -> Vec<TemplateMatch> {
+    // Anchors in the same region share a lookup (and its cache)
+    let by_region = anchors
+        .into_iter()
+        .into_group_map_by(|pos| (pos.x.div_euclid(512), pos.z.div_euclid(512)));
+
+    by_region
+        .into_values()
+        .collect_vec()
+        .into_par_iter()
+        .flat_map_iter(|anchors| {
+            let mut lookup = BlockLookup::new(path);
+
+            anchors
+                .iter()
+                .flat_map(|position| matches_at(&mut lookup, anchor, orientations, position))
+                .collect_vec()
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .unique_by(|found| (found.bounds.min.clone(), found.degrees, found.mirrored))
+        .collect()
+}
+
+// Every placement of the template that puts the anchor cell at a position
+fn matches_at(
+    lookup: &mut BlockLookup,
+    anchor: &Cell,
+    orientations: &[Orientation],
+    position: &Point,
+) -> Vec<TemplateMatch> {
+    let mut found = vec![];
+
+    for orientation in orientations {
+        let cells = &orientation.template.cells;
+
+        // Wherever the anchor cell ended up after turning the template
+        for placed in cells
+            .iter()
+            .filter(|cell| cell.pattern() == anchor.pattern())
+        {
+            let origin = Point {
+                x: position.x - placed.offset.x,
+                y: position.y - placed.offset.y,
+                z: position.z - placed.offset.z,
+            };
+
+            let matches = cells.iter().all(|cell| {
+                let point = Point {
+                    x: origin.x + cell.offset.x,
+                    y: origin.y + cell.offset.y,
+                    z: origin.z + cell.offset.z,
+                };
+
+                lookup
+                    .block_at(&point)
+                    .is_some_and(|name| cell.blocks.matches(name))
+            });
+
+            if matches {
+                let size = orientation.template.size();
+
+                found.push(TemplateMatch {
+                    bounds: Bounds {
+                        max: Point {
+                            x: origin.x + size.x - 1,
+                            y: origin.y + size.y - 1,
+                            z: origin.z + size.z - 1,
+                        },
+                        min: origin,
+                    },
+                    degrees: orientation.degrees,
+                    mirrored: orientation.mirrored,
+                });
+            }
+        }
+    }
+
+    found
+}
+
+#[derive(Deserialize)]
+struct StructureFile {
+    #[serde(default)]
+    palette: Vec<StructurePaletteEntry>,
+
+    /// Structures with random variants, like shipwrecks, keep several palettes
+    #[serde(default)]
+    palettes: Vec<Vec<StructurePaletteEntry>>,
+
+    #[serde(default)]
+    blocks: Vec<StructureBlock>,
+}
+
+#[derive(Deserialize)]
+struct StructurePaletteEntry {
+    #[serde(rename = "Name")]
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct StructureBlock {
+    pos: Vec<i32>,
+    state: i32,
+}
+
+fn structure_blocks(data: &[u8]) -> Result<Vec<(Point, String)>, String> {
+    let structure = from_bytes::<StructureFile>(data).map_err(|err| err.to_string())?;
+    let palette = match structure.palette.is_empty() {
+        true => structure.palettes.into_iter().next().unwrap_or_default(),
+        false => structure.palette,
+    };
+
+    structure
+        .blocks
+        .into_iter()
+        .map(|block| {
+            let name = palette
+                .get(block.state as usize)
+                .ok_or_else(|| format!("unknown block state {}", block.state))?;
+
+            match block.pos[..] {
+                [x, y, z] => Ok((
+                    Point {
+                        x: x as i64,
+                        y: y as i64,
+                        z: z as i64,
+                    },
+                    name.name.clone(),
+                )),
+                _ => Err("block positions should have three coordinates".to_string()),
+            }
+        })
+        .collect()
+}
+
+// Version 3 of the format moves everything under `Schematic` and the blocks under `Blocks`
+#[derive(Deserialize)]
+struct SchematicFile {
+    #[serde(rename = "Schematic")]
+    schematic: Option<Schematic>,
+
+    #[serde(flatten)]
+    version_2: Option<Schematic>,
+}
+
+#[derive(Deserialize)]
+struct Schematic {
+    #[serde(rename = "Width")]
+    width: i32,
+
+    #[serde(rename = "Height")]
+    height: i32,
+
+    #[serde(rename = "Length")]
+    length: i32,
+
+    #[serde(rename = "Blocks")]
+    blocks: Option<SchematicBlocks>,
+
+    #[serde(rename = "Palette")]
+    palette: Option<HashMap<String, i32>>,
+
+    #[serde(rename = "BlockData")]
+    block_data: Option<ByteArray>,
+}
+
+#[derive(Deserialize)]
+struct SchematicBlocks {
+    #[serde(rename = "Palette")]
+    palette: HashMap<String, i32>,
+
+    #[serde(rename = "Data")]
+    data: ByteArray,
+}
+
+fn schematic_blocks(data: &[u8]) -> Result<Vec<(Point, String)>, String> {
+    let file = from_bytes::<SchematicFile>(data).map_err(|err| err.to_string())?;
+    let schematic = file
+        .schematic
+        .or(file.version_2)
+        .ok_or("not a Sponge schematic")?;
+
+    let (palette, data) = match (schematic.blocks, schematic.palette, schematic.block_data) {
+        (Some(blocks), _, _) => (blocks.palette, blocks.data),
+        (None, Some(palette), Some(data)) => (palette, data),
+        _ => return Err("the schematic has no blocks".to_string()),
+    };
+
+    // Block states are written like `minecraft:oak_stairs[facing=east]`
+    let names = palette
+        .into_iter()
+        .map(|(state, index)| {
+            (
+                index,
+                state.split('[').next().unwrap_or_default().to_string(),
+            )
+        })
+        .collect::<HashMap<_, _>>();
+
+    let (width, length) = (schematic.width as i64, schematic.length as i64);
+    let indices = read_varints(&data)?;
+
+    if indices.len() as i64 != width * length * schematic.height as i64 {
+        return Err("the block data doesn't fit the schematic's size".to_string());
+    }
+
+    indices
+        .into_iter()
+        .enumerate()
+        .map(|(i, index)| {
+            let i = i as i64;
+            let name = names
+                .get(&index)
+                .ok_or_else(|| format!("unknown palette index {}", index))?;
+
+            Ok((
+                Point {
+                    x: i % width,
+                    y: i / (width * length),
+                    z: (i / width) % length,
+                },
+                name.clone(),
+            ))
+        })
+        .collect()
+}
+
+// Palette indices are stored as variable length integers, 7 bits per byte
+fn read_varints(data: &[i8]) -> Result<Vec<i32>, String> {
+    let mut values = vec![];
+    let (mut value, mut shift) = (0i32, 0);
+
+    for byte in data.iter().map(|byte| *byte as u8) {
+        if shift >= 32 {
+            return Err("a palette index in the block data is too long".to_string());
+        }
+
+        value |= ((byte & 0x7F) as i32) << shift;
+        shift += 7;
+
+        if byte & 0x80 == 0 {
+            values.push(value);
+            value = 0;
+            shift = 0;
+        }
+    }
+
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use fastnbt::{nbt, to_bytes};
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+
+    #[test]
+    fn orientations_of_an_l() {
+        let template = Template::parse("A=minecraft:stone B=minecraft:dirt AB/A.").unwrap();
+        assert_eq!(template.cells.len(), 3);
+        assert_eq!(template.size(), Point { x: 2, y: 1, z: 2 });
+
+        // An L shape has four distinct turns; mirroring gives the same shapes again
+        // but with the dirt on the other arm
+        assert_eq!(template.orientations(false, false).len(), 1);
+        assert_eq!(template.orientations(true, false).len(), 4);
+        assert_eq!(template.orientations(true, true).len(), 8);
+
+        let turned = template.turned(1, false);
+        let dirt = turned
+            .cells
+            .iter()
+            .find(|cell| cell.blocks.matches("minecraft:dirt"))
+            .unwrap();
+        assert_eq!(dirt.offset, Point { x: 1, y: 0, z: 1 });
+
+        let square = Template::parse("A=minecraft:stone AA/AA").unwrap();
+        assert_eq!(square.orientations(true, true).len(), 1);
+
+        assert!(Template::parse("A=minecraft:stone AB").is_err());
+    }
+
+    #[test]
+    fn finds_a_placed_template() {
+        let template =
+            Template::parse("A=minecraft:obsidian G=minecraft:gold_block AG/A.").unwrap();
+        let anchors = template.anchors().unwrap();
+        assert_eq!(anchors.len(), 2);
+
+        let mut lookup = BlockLookup::new(Path::new("world"));
+        lookup.set_section((0, 0, 0), |point| match (point.x, point.y, point.z) {
+            (4, 4, 4) | (4, 4, 5) => ("minecraft:obsidian", 0),
+            (5, 4, 4) => ("minecraft:gold_block", 0),
+            _ => ("minecraft:stone", 0),
+        });
+
+        let gold = anchors[1];
+        let found = matches_at(
+            &mut lookup,
+            gold,
+            &template.orientations(true, true),
+            &Point { x: 5, y: 4, z: 4 },
+        );
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].bounds.min, Point { x: 4, y: 4, z: 4 });
+        assert_eq!(found[0].bounds.max, Point { x: 5, y: 4, z: 5 });
+        assert_eq!((found[0].degrees, found[0].mirrored), (0, false));
+
+        assert!(Template::parse("A=minecraft:air A")
+            .unwrap()
+            .anchors()
+            .is_err());
+    }
+
+    #[test]
+    fn structure_and_schematic_files() {
+        let structure = nbt!({
+            "size": [2, 2, 1],
+            "palette": [
+                {"Name": "minecraft:obsidian"},
+                {"Name": "minecraft:air"},
+                {"Name": "minecraft:structure_void"},
+            ],
+            "blocks": [
+                {"pos": [0, 0, 0], "state": 0},
+                {"pos": [0, 1, 0], "state": 1},
+                {"pos": [1, 0, 0], "state": 2},
+            ],
+        });
+
+        // The same blocks, with stairs instead of the structure void
+        let palette = nbt!({
+            "minecraft:obsidian": 0,
+            "minecraft:air": 1,
+            "minecraft:oak_stairs[facing=east]": 2,
+        });
+        let version_2 = nbt!({
+            "Version": 2,
+            "Width": 2i16,
+            "Height": 2i16,
+            "Length": 1i16,
+            "Palette": palette.clone(),
+            "BlockData": [B; 0, 2, 1, 1],
+        });
+        let version_3 = nbt!({"Schematic": {
+            "Version": 3,
+            "Width": 2i16,
+            "Height": 2i16,
+            "Length": 1i16,
+            "Blocks": {"Palette": palette, "Data": [B; 0, 2, 1, 1]},
+        }});
+
+        let dir = std::env::temp_dir().join(format!("mcworldgen-templates-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let read = |name: &str, nbt: &fastnbt::Value| {
+            let path = dir.join(name);
+            let mut file = GzEncoder::new(File::create(&path).unwrap(), Compression::default());
+            file.write_all(&to_bytes(nbt).unwrap()).unwrap();
+            file.finish().unwrap();
+
+            Template::read(&path)
+                .unwrap()
+                .cells
+                .iter()
+                .map(|cell| (cell.offset.clone(), cell.pattern()))
+                .collect_vec()
+        };
+
+        let origin = Point { x: 0, y: 0, z: 0 };
+        let above = Point { x: 0, y: 1, z: 0 };
+        let beside = Point { x: 1, y: 0, z: 0 };
+
+        assert_eq!(
+            read("well.nbt", &structure),
+            vec![
+                (origin.clone(), "minecraft:obsidian".to_string()),
+                (above.clone(), AIR.to_string()),
+            ]
+        );
+
+        let schematic = vec![
+            (origin, "minecraft:obsidian".to_string()),
+            (beside, "minecraft:oak_stairs".to_string()),
+            (above.clone(), AIR.to_string()),
+            (Point { x: 1, ..above }, AIR.to_string()),
+        ];
+        assert_eq!(read("well_v2.schem", &version_2), schematic);
+        assert_eq!(read("well_v3.schem", &version_3), schematic);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn varints() {
+        assert_eq!(
+            read_varints(&[1, 0, -128, 1, 127]),
+            Ok(vec![1, 0, 128, 127])
+        );
+        assert_eq!(read_varints(&[-1, -1, -1, -1, 7]), Ok(vec![i32::MAX]));
+        assert!(read_varints(&[-1, -1, -1, -1, -1, 1]).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use itertools::Itertools;
+
+use super::{
+    stats::{mean, quantile},
+    veins::Vein,
+};
+
+/// Aggregate statistics over all veins of one block type
+pub struct VeinStats {
+    pub group: String,
+    pub veins: usize,
+
+    /// Veins made of a single block
+    pub single: usize,
+
+    pub min_size: usize,
+    pub median_size: usize,
+    pub p90_size: usize,
+    pub max_size: usize,
+
+    /// The mean volume of the veins' bounding boxes
+    pub mean_volume: f64,
+
+    /// The mean Y level of the veins' centers
+    pub mean_center_y: f64,
+}
+
+impl VeinStats {
+    /// Statistics for each block type, keyed by the veins' normalized names
+    pub fn by_group(veins: &[Vein]) -> Vec<VeinStats> {
+        veins
+            .iter()
+            .into_group_map_by(|vein| vein.blocks[0].group.clone())
+            .into_iter()
+            .sorted_by(|a, b| a.0.cmp(&b.0))
+            .map(|(group, veins)| Self::of(group, &veins))
+            .collect()
+    }
+
+    fn of(group: String, veins: &[&Vein]) -> VeinStats {
+        let sizes = veins
+            .iter()
+            .map(|vein| vein.blocks.len() as f64)
+            .sorted_by(f64::total_cmp)
+            .collect_vec();
+
+        let size = |q: f64| quantile(&sizes, q).unwrap_or(0.0) as usize;
+
+        let volumes = veins
+            .iter()
+            .map(|vein| vein.bounds.volume() as f64)
+            .collect_vec();
+        let centers = veins.iter().map(|vein| vein.center.y as f64).collect_vec();
+
+        VeinStats {
+            group,
+            veins: veins.len(),
+            single: veins.iter().filter(|vein| vein.blocks.len() == 1).count(),
+            min_size: size(0.0),
+            median_size: size(0.5),
+            p90_size: size(0.9),
+            max_size: size(1.0),
+            mean_volume: mean(&volumes).unwrap_or(0.0),
+            mean_center_y: mean(&centers).unwrap_or(0.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{
+        blocks::BlockDescriptor,
+        veins::{find_veins, Linking},
+    };
+
+    #[test]
+    fn stats_per_group() {
+        // Diamond veins are bars along X, two levels apart, and iron veins are a
+        // column and a bar
+        let diamonds = [1, 1, 2, 3, 4, 5, 6, 7, 8, 10]
+            .into_iter()
+            .enumerate()
+            .flat_map(|(i, size)| {
+                let name = match i {
+                    0 => "minecraft:deepslate_diamond_ore",
+                    _ => "minecraft:diamond_ore",
+                };
+
+                (0..size).map(move |x| BlockDescriptor::new(name, x, 2 * i as i64, 0))
+            });
+        let iron = (-60..-55)
+            .map(|y| BlockDescriptor::new("minecraft:iron_ore", 100, y, 0))
+            .chain((0..3).map(|x| BlockDescriptor::new("minecraft:iron_ore", x, 20, 100)));
+
+        let veins = find_veins(diamonds.chain(iron).collect(), Linking::default()).collect_vec();
+        let stats = VeinStats::by_group(&veins);
+
+        assert_eq!(stats.len(), 2);
+
+        let diamond = &stats[0];
+        assert_eq!(diamond.group, "minecraft:diamond_ore");
+        assert_eq!((diamond.veins, diamond.single), (10, 2));
+        assert_eq!(
+            (
+                diamond.min_size,
+                diamond.median_size,
+                diamond.p90_size,
+                diamond.max_size
+            ),
+            (1, 5, 8, 10)
+        );
+        assert_eq!(diamond.mean_volume, 4.7);
+        assert_eq!(diamond.mean_center_y, 9.0);
+
+        let iron = &stats[1];
+        assert_eq!(iron.group, "minecraft:iron_ore");
+        assert_eq!((iron.veins, iron.single), (2, 0));
+        assert_eq!(
+            (
+                iron.min_size,
+                iron.median_size,
+                iron.p90_size,
+                iron.max_size
+            ),
+            (3, 5, 5, 5)
+        );
+        assert_eq!(iron.mean_volume, 4.0);
+        assert_eq!(iron.mean_center_y, -19.0);
+    }
+}
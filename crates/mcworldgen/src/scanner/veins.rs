@@ -1,11 +1,14 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use itertools::Itertools;
 use partitions::PartitionVec;
 
 use crate::scanner::bounds::Bounds;
 
-use super::{blocks::BlockDescriptor, points::Point};
+use super::{
+    blocks::BlockDescriptor,
+    points::{Connectivity, Point},
+};
 
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct Vein {
@@ -23,6 +26,17 @@ impl Vein {
         }
     }
 
+    /// How many blocks of each raw block type the vein is made of
+    pub fn members(&self) -> BTreeMap<&str, usize> {
+        let mut members = BTreeMap::new();
+
+        for block in &self.blocks {
+            *members.entry(block.name.as_str()).or_default() += 1;
+        }
+
+        members
+    }
+
     pub fn with_computed_bounds(mut self) -> Self {
         self.bounds = self.compute_bounds();
         self.center = self.bounds.center();
@@ -85,17 +99,35 @@ impl PartialOrd for Vein {
     }
 }
 
-pub fn find_veins(blocks: Vec<BlockDescriptor>) -> impl Iterator<Item = Vein> {
+/// How blocks of the same type are linked into veins
+#[derive(Clone, Copy, Debug)]
+pub struct Linking {
+    pub connectivity: Connectivity,
+
+    /// How many blocks of something else may separate two blocks of the same vein
+    pub max_gap: u32,
+}
+
+impl Default for Linking {
+    fn default() -> Self {
+        Self {
+            connectivity: Connectivity::Faces,
+            max_gap: 0,
+        }
+    }
+}
+
+pub fn find_veins(blocks: Vec<BlockDescriptor>, linking: Linking) -> impl Iterator<Item = Vein> {
     let mut blocks = PartitionVec::from_iter(blocks);
 
     let mut idx_to_pos = HashMap::new();
     let mut pos_to_idx = HashMap::new();
-    let mut pos_to_name = HashMap::new();
+    let mut pos_to_group = HashMap::new();
 
     for (idx, block) in blocks.iter().enumerate() {
         idx_to_pos.insert(idx, block.pos());
         pos_to_idx.insert(block.pos(), idx);
-        pos_to_name.insert(block.pos(), block.name.clone());
+        pos_to_group.insert(block.pos(), block.group.clone());
     }
 
     // Label every block using a union-find structure
@@ -106,9 +138,9 @@ pub fn find_veins(blocks: Vec<BlockDescriptor>) -> impl Iterator<Item = Vein> {
 
         let neighbors = block
             .pos()
-            .neighbors()
-            .flat_map(|pos| pos_to_name.get(&pos).map(|name| (pos, name)))
-            .filter(|(_, name)| **name == block.name)
+            .neighbors(linking.connectivity, linking.max_gap as i64 + 1)
+            .flat_map(|pos| pos_to_group.get(&pos).map(|group| (pos, group)))
+            .filter(|(_, group)| **group == block.group)
             .map(|(pos, _)| pos_to_idx.get(&pos).unwrap())
             .collect_vec();
 
@@ -126,7 +158,52 @@ pub fn find_veins(blocks: Vec<BlockDescriptor>) -> impl Iterator<Item = Vein> {
         .map(|vein_blocks| {
             Vein::from_iter(vein_blocks.into_iter().map(|(_, b)| b.clone())).with_computed_bounds()
         })
-        .filter(|vein| vein.blocks.len() > 1)
         .collect_vec()
         .into_iter()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sizes(blocks: &[(i64, i64, i64)], linking: Linking) -> Vec<usize> {
+        let blocks = blocks
+            .iter()
+            .map(|(x, y, z)| BlockDescriptor::new("minecraft:diamond_ore", *x, *y, *z))
+            .collect_vec();
+
+        find_veins(blocks, linking)
+            .map(|vein| vein.blocks.len())
+            .sorted()
+            .collect()
+    }
+
+    #[test]
+    fn connectivity() {
+        let diagonal = [(0, 0, 0), (1, 1, 0), (2, 2, 1)];
+        let linking = |connectivity| Linking {
+            connectivity,
+            max_gap: 0,
+        };
+
+        assert_eq!(
+            sizes(&diagonal, linking(Connectivity::Faces)),
+            vec![1, 1, 1]
+        );
+        assert_eq!(sizes(&diagonal, linking(Connectivity::Edges)), vec![1, 2]);
+        assert_eq!(sizes(&diagonal, linking(Connectivity::Corners)), vec![3]);
+    }
+
+    #[test]
+    fn gaps() {
+        let gapped = [(0, 0, 0), (2, 0, 0), (5, 0, 0)];
+        let linking = |max_gap| Linking {
+            connectivity: Connectivity::Faces,
+            max_gap,
+        };
+
+        assert_eq!(sizes(&gapped, linking(0)), vec![1, 1, 1]);
+        assert_eq!(sizes(&gapped, linking(1)), vec![1, 2]);
+        assert_eq!(sizes(&gapped, linking(2)), vec![3]);
+    }
+}
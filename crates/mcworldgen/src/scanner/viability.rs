@@ -0,0 +1,268 @@
+use super::{
+    blocks::{BlockEntity, SpawnData},
+    census::AIR_BLOCKS,
+    lookup::BlockLookup,
+    points::Point,
+};
+
+/// The brightest block light monsters spawn in without custom spawn rules
+const MONSTER_BLOCK_LIGHT: u8 = 11;
+
+/// What a mob needs to spawn
+struct Placement {
+    /// How many blocks of air the mob needs above its feet
+    height: i64,
+
+    /// Flying mobs can spawn without a block to stand on
+    needs_floor: bool,
+
+    /// The allowed block light range, if it's limited
+    block_light: Option<(u8, u8)>,
+
+    /// The allowed sky light range, if it's limited
+    sky_light: Option<(u8, u8)>,
+}
+
+/// Whether a mob can spawn at a position, from best to worst
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum Outcome {
+    Valid,
+
+    /// Valid as far as the blocks go, but the light level isn't stored in the world
+    UnknownLight,
+    WrongLight,
+    NoFloor,
+    Blocked,
+}
+
+impl Placement {
+    fn of_mob(mob: &str) -> Self {
+        let (height, needs_floor, light) = match mob {
+            "minecraft:cave_spider"
+            | "minecraft:spider"
+            | "minecraft:magma_cube"
+            | "minecraft:slime"
+            | "minecraft:endermite" => (1, true, true),
+            "minecraft:silverfish" => (1, true, false),
+            "minecraft:blaze" => (2, false, true),
+            "minecraft:vex" | "minecraft:bat" => (1, false, true),
+            "minecraft:wither_skeleton" | "minecraft:enderman" => (3, true, true),
+            _ => (2, true, true),
+        };
+
+        Self {
+            height,
+            needs_floor,
+            block_light: light.then_some((0, MONSTER_BLOCK_LIGHT)),
+            sky_light: None,
+        }
+    }
+
+    /// The placement of a spawner's mob, where custom spawn rules replace its usual light limits
+    fn of_spawn_data(data: &SpawnData) -> Self {
+        let mut placement = Self::of_mob(data.mob().unwrap_or_default());
+
+        if let Some(rules) = &data.custom_spawn_rules {
+            let clamp = |(min, max): (i32, i32)| (min.clamp(0, 15) as u8, max.clamp(0, 15) as u8);
+
+            placement.block_light = rules.block_light_limit.as_ref().map(|l| clamp(l.range()));
+            placement.sky_light = rules.sky_light_limit.as_ref().map(|l| clamp(l.range()));
+        }
+
+        placement
+    }
+
+    fn check(&self, lookup: &mut BlockLookup, feet: &Point) -> Outcome {
+        let Point { x, y, z } = *feet;
+
+        let room = (0..self.height).all(|h| {
+            lookup
+                .block_at(&Point { x, y: y + h, z })
+                .is_some_and(|name| AIR_BLOCKS.contains(&name))
+        });
+
+        if !room {
+            return Outcome::Blocked;
+        }
+
+        let floor = lookup
+            .block_at(&Point { x, y: y - 1, z })
+            .is_some_and(|name| !is_passable(name));
+
+        if self.needs_floor && !floor {
+            return Outcome::NoFloor;
+        }
+
+        let within = |level: u8, limit: Option<(u8, u8)>| {
+            limit.is_none_or(|(min, max)| (min..=max).contains(&level))
+        };
+
+        match lookup.light_at(feet) {
+            // Dimensions without sky light skip its check
+            Some(light)
+                if within(light.block, self.block_light)
+                    && light.sky.is_none_or(|sky| within(sky, self.sky_light)) =>
+            {
+                Outcome::Valid
+            }
+            Some(_) => Outcome::WrongLight,
+            None => Outcome::UnknownLight,
+        }
+    }
+}
+
+/// How many of the positions a spawner picks from can actually hold its mob
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct Viability {
+    pub positions: usize,
+    pub valid: usize,
+
+    /// Positions without enough air for the mob
+    pub blocked: usize,
+
+    /// Positions without a block to stand on
+    pub no_floor: usize,
+
+    /// Positions outside the mob's light limits
+    pub wrong_light: usize,
+
+    /// Valid positions whose light level isn't stored in the world
+    pub unknown_light: usize,
+}
+
+impl Viability {
+    /// The share of positions where the mob can spawn
+    pub fn score(&self) -> f64 {
+        self.valid as f64 / self.positions.max(1) as f64
+    }
+}
+
+/// Check every position in a spawner's spawn volume: `SpawnRange` blocks to either
+/// side horizontally and one block above or below it
+///
+/// A position counts as valid if any of the mobs the spawner picks from can spawn there.
+pub fn check_viability(entity: &BlockEntity, lookup: &mut BlockLookup) -> Viability {
+    let mut placements = entity
+        .spawn_data
+        .iter()
+        .chain(
+            entity
+                .spawn_potentials
+                .iter()
+                .flatten()
+                .filter_map(|potential| potential.data.as_ref()),
+        )
+        .filter(|data| data.mob().is_some())
+        .map(Placement::of_spawn_data)
+        .collect::<Vec<_>>();
+
+    if placements.is_empty() {
+        placements.push(Placement::of_mob(""));
+    }
+
+    let range = entity.spawn_range.unwrap_or(4) as i64;
+    let mut viability = Viability::default();
+
+    for x in entity.x - range..=entity.x + range {
+        for z in entity.z - range..=entity.z + range {
+            for y in entity.y - 1..=entity.y + 1 {
+                let feet = Point { x, y, z };
+                viability.positions += 1;
+
+                let outcome = placements
+                    .iter()
+                    .map(|placement| placement.check(lookup, &feet))
+                    .min()
+                    .unwrap_or(Outcome::Blocked);
+
+                match outcome {
+                    Outcome::Valid => viability.valid += 1,
+                    Outcome::UnknownLight => {
+                        viability.valid += 1;
+                        viability.unknown_light += 1;
+                    }
+                    Outcome::WrongLight => viability.wrong_light += 1,
+                    Outcome::NoFloor => viability.no_floor += 1,
+                    Outcome::Blocked => viability.blocked += 1,
+                }
+            }
+        }
+    }
+
+    viability
+}
+
+fn is_passable(name: &str) -> bool {
+    AIR_BLOCKS.contains(&name) || name == "minecraft:water" || name == "minecraft:lava"
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use fastnbt::nbt;
+
+    use super::*;
+
+    #[test]
+    fn spawn_positions_in_an_air_pocket() {
+        // A 3x3x3 pocket of air around a spawner at 8, 8, 8 with a stone floor
+        // below it, lit by a torch along x = 9
+        let mut lookup = BlockLookup::new(Path::new("world/DIM-1"));
+        lookup.set_section((0, 0, 0), |point| {
+            let pocket = [point.x, point.y, point.z]
+                .iter()
+                .all(|c| (7..=9).contains(c));
+            let light = if point.x == 9 { 14 } else { 0 };
+
+            match (point.x, point.y, point.z) {
+                (8, 8, 8) => ("minecraft:spawner", 0),
+                _ if pocket => ("minecraft:cave_air", light),
+                _ => ("minecraft:stone", 0),
+            }
+        });
+
+        let spawner = |potentials| {
+            BlockEntity::from_nbt(nbt!({
+                "id": "minecraft:mob_spawner",
+                "x": 8,
+                "y": 8,
+                "z": 8,
+                "SpawnRange": 1,
+                "SpawnData": {"entity": {"id": "minecraft:zombie"}},
+                "SpawnPotentials": potentials,
+            }))
+            .unwrap()
+        };
+
+        // Zombies only fit with their feet at y = 7, where the x = 9 column is too bright
+        let dark = check_viability(&spawner(nbt!([])), &mut lookup);
+        assert_eq!(
+            dark,
+            Viability {
+                positions: 27,
+                valid: 5,
+                blocked: 11,
+                no_floor: 8,
+                wrong_light: 3,
+                unknown_light: 0,
+            }
+        );
+
+        // A potential whose spawn rules allow any block light makes the bright column count
+        let lit = check_viability(
+            &spawner(nbt!([{
+                "weight": 1,
+                "data": {
+                    "entity": {"id": "minecraft:zombie"},
+                    "custom_spawn_rules": {
+                        "block_light_limit": {"min_inclusive": 0, "max_inclusive": 15},
+                    },
+                },
+            }])),
+            &mut lookup,
+        );
+        assert_eq!(lit.valid, 8);
+        assert_eq!(lit.wrong_light, 0);
+    }
+}
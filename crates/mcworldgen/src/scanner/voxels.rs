@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+use super::{points::Point, veins::Vein};
+
+/// A sparse store of values at block positions
+pub struct VoxelStore<T> {
+    voxels: HashMap<Point, T>,
+}
+
+impl<T> VoxelStore<T> {
+    pub fn new() -> Self {
+        Self {
+            voxels: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, point: Point, value: T) {
+        self.voxels.insert(point, value);
+    }
+
+    pub fn get(&self, point: &Point) -> Option<&T> {
+        self.voxels.get(point)
+    }
+
+    pub fn len(&self) -> usize {
+        self.voxels.len()
+    }
+}
+
+impl VoxelStore<usize> {
+    /// The index of the vein each block belongs to
+    pub fn of_veins(veins: &[Vein]) -> Self {
+        let mut store = Self::new();
+
+        for (index, vein) in veins.iter().enumerate() {
+            for block in &vein.blocks {
+                store.insert(block.pos(), index);
+            }
+        }
+
+        store
+    }
+}